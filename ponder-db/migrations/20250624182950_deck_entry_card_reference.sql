@@ -0,0 +1,18 @@
+-- `card.card_id` isn't unique (card faces share the Scryfall id) so it can't be a foreign key
+-- parent, and SQLite rejects every write to `card` while that reference exists.
+create table deck_entry_new(
+    deck_id integer not null,
+    card_id text not null,
+    entry_type text not null check (entry_type in ('main', 'sideboard', 'commander')),
+    quantity integer not null default 1,
+    primary key (deck_id, card_id, entry_type),
+    foreign key (deck_id) references deck(id)
+);
+
+insert into deck_entry_new select deck_id, card_id, entry_type, quantity from deck_entry;
+drop table deck_entry;
+alter table deck_entry_new rename to deck_entry;
+
+create index if not exists idx_deck_entry_deck on deck_entry(deck_id);
+create index if not exists idx_deck_entry_card on deck_entry(card_id);
+create index if not exists idx_deck_entry_type on deck_entry(entry_type);
@@ -0,0 +1,12 @@
+-- Keep the printed value alongside the sortable one so `*`, `1+*`, `X` etc. aren't lost
+alter table card add column power_text text;
+alter table card add column toughness_text text;
+alter table card add column loyalty_text text;
+alter table card add column defense integer;
+alter table card add column defense_text text;
+
+update card set power_text = cast(power as text) where power is not null;
+update card set toughness_text = cast(toughness as text) where toughness is not null;
+update card set loyalty_text = cast(loyalty as text) where loyalty is not null;
+
+create index if not exists idx_card_defense on card(defense);
@@ -0,0 +1,31 @@
+alter table card add column nonfoil boolean not null default 0;
+alter table card add column highres_image boolean not null default 0;
+alter table card add column collector_number text;
+alter table card add column released_at text; -- E.g. 2017-04-28
+alter table card add column edhrec_rank integer;
+alter table card add column frame text; -- E.g. 2015
+alter table card add column security_stamp text;
+alter table card add column watermark text;
+
+create table if not exists card_frame_effect (
+    card_id integer not null references card(id),
+    frame_effect text not null,
+    primary key (card_id, frame_effect)
+);
+
+create table if not exists card_finish (
+    card_id integer not null references card(id),
+    finish text not null, -- E.g. nonfoil, foil, etched
+    primary key (card_id, finish)
+);
+
+create table if not exists card_produced_mana (
+    card_id integer not null references card(id),
+    mana text not null, -- E.g. W, U, C
+    primary key (card_id, mana)
+);
+
+create index if not exists idx_card_set_collector_number on card(set_short, collector_number);
+create index if not exists idx_card_released_at on card(released_at);
+create index if not exists idx_card_edhrec_rank on card(edhrec_rank);
+create index if not exists idx_card_oracle_id on card(oracle_id);
@@ -0,0 +1,27 @@
+-- Zones beyond main/sideboard/commander, `entry_type` becomes `zone`
+create table deck_entry_new(
+    deck_id integer not null,
+    card_id text not null,
+    zone text not null check (zone in (
+        'main',
+        'sideboard',
+        'commander',
+        'maybeboard',
+        'companion',
+        'signature_spell',
+        'attractions',
+        'contraptions',
+        'wishboard'
+    )),
+    quantity integer not null default 1,
+    primary key (deck_id, card_id, zone),
+    foreign key (deck_id) references deck(id)
+);
+
+insert into deck_entry_new select deck_id, card_id, entry_type, quantity from deck_entry;
+drop table deck_entry;
+alter table deck_entry_new rename to deck_entry;
+
+create index if not exists idx_deck_entry_deck on deck_entry(deck_id);
+create index if not exists idx_deck_entry_card on deck_entry(card_id);
+create index if not exists idx_deck_entry_zone on deck_entry(zone);
@@ -0,0 +1,18 @@
+create table if not exists deck_revision(
+    id integer primary key,
+    deck_id integer not null,
+    created_at text not null default (strftime('%Y-%m-%d %H:%M:%S', 'now')),
+    note text,
+    foreign key (deck_id) references deck(id)
+);
+
+create table if not exists deck_revision_entry(
+    revision_id integer not null,
+    card_id text not null,
+    zone text not null,
+    quantity integer not null,
+    primary key (revision_id, card_id, zone),
+    foreign key (revision_id) references deck_revision(id)
+);
+
+create index if not exists idx_deck_revision_deck on deck_revision(deck_id);
@@ -0,0 +1,10 @@
+create table if not exists deck_entry_tag(
+    deck_id integer not null,
+    card_id text not null,
+    zone text not null,
+    tag text not null,
+    primary key (deck_id, card_id, zone, tag),
+    foreign key (deck_id) references deck(id)
+);
+
+create index if not exists idx_deck_entry_tag_tag on deck_entry_tag(deck_id, tag);
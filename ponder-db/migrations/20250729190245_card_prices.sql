@@ -0,0 +1,11 @@
+-- Prices change daily so they're replaced on every update, unlike the card rows.
+-- Keyed by the Scryfall id as every face of a card shares its prices.
+create table if not exists card_price(
+    card_id text primary key,
+    usd real,
+    usd_foil real,
+    usd_etched real,
+    eur real,
+    eur_foil real,
+    tix real
+);
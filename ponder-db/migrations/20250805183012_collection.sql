@@ -0,0 +1,13 @@
+-- Owned copies of each printing. Unknown conditions, languages and locations are stored as
+-- empty strings so copies that differ only by a missing value still share a row.
+create table if not exists collection_entry(
+    card_id text not null,
+    finish text not null default 'nonfoil',
+    condition text not null default '',
+    language text not null default '',
+    location text not null default '',
+    quantity integer not null default 1,
+    primary key (card_id, finish, condition, language, location)
+);
+
+create index if not exists idx_collection_entry_location on collection_entry(location);
@@ -0,0 +1,46 @@
+-- Foil and etched copies in decks, as CSV exports from deckbuilding sites record them. A card can
+-- be in a zone in more than one finish, so the finish is part of the key. Tags stay per card and
+-- zone, shared by its finishes.
+create table deck_entry_new(
+    deck_id integer not null,
+    card_id text not null,
+    zone text not null check (zone in (
+        'main',
+        'sideboard',
+        'commander',
+        'maybeboard',
+        'companion',
+        'signature_spell',
+        'attractions',
+        'contraptions',
+        'wishboard'
+    )),
+    finish text not null default 'nonfoil',
+    quantity integer not null default 1,
+    primary key (deck_id, card_id, zone, finish),
+    foreign key (deck_id) references deck(id)
+);
+
+insert into deck_entry_new(deck_id, card_id, zone, quantity)
+select deck_id, card_id, zone, quantity from deck_entry;
+drop table deck_entry;
+alter table deck_entry_new rename to deck_entry;
+
+create index if not exists idx_deck_entry_deck on deck_entry(deck_id);
+create index if not exists idx_deck_entry_card on deck_entry(card_id);
+create index if not exists idx_deck_entry_zone on deck_entry(zone);
+
+create table deck_revision_entry_new(
+    revision_id integer not null,
+    card_id text not null,
+    zone text not null,
+    finish text not null default 'nonfoil',
+    quantity integer not null,
+    primary key (revision_id, card_id, zone, finish),
+    foreign key (revision_id) references deck_revision(id)
+);
+
+insert into deck_revision_entry_new(revision_id, card_id, zone, quantity)
+select revision_id, card_id, zone, quantity from deck_revision_entry;
+drop table deck_revision_entry;
+alter table deck_revision_entry_new rename to deck_revision_entry;
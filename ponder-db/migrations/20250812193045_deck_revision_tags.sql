@@ -0,0 +1,8 @@
+create table if not exists deck_revision_entry_tag(
+    revision_id integer not null,
+    card_id text not null,
+    zone text not null,
+    tag text not null,
+    primary key (revision_id, card_id, zone, tag),
+    foreign key (revision_id) references deck_revision(id)
+);
@@ -1,6 +1,101 @@
-use sqlx::FromRow;
+use crate::scryfall::{BorderColor, ImageStatus, Layout, Rarity, SetType};
+use sqlx::{
+    Decode, FromRow, Sqlite, Type,
+    error::BoxDynError,
+    sqlite::{SqliteTypeInfo, SqliteValueRef},
+};
 
-#[derive(Debug, FromRow)]
+/// Power, toughness, loyalty or defense as printed on the card
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stat {
+    /// A plain number, e.g. `3` or `-1`
+    Fixed(i32),
+    /// A characteristic-defining value with an optional fixed part, e.g. `*` or `1+*`
+    Variable(i32),
+    /// `X`, as found on some planeswalkers
+    X,
+    /// `?`, as found on some Un-cards
+    Unknown,
+    /// `∞`
+    Infinite,
+    /// Anything else Scryfall hands us (e.g. `1.5`, `*²` or `+1`)
+    Other(String),
+}
+
+impl Stat {
+    /// Sortable value of the stat, if there is a sensible one.
+    ///
+    /// Variable stats use their fixed part (`*` is 0, `1+*` is 1) and `∞` sorts above everything else.
+    pub fn value(&self) -> Option<i32> {
+        match self {
+            Self::Fixed(v) | Self::Variable(v) => Some(*v),
+            Self::Infinite => Some(i32::MAX),
+            Self::X | Self::Unknown | Self::Other(_) => None,
+        }
+    }
+
+    pub fn is_variable(&self) -> bool {
+        !matches!(self, Self::Fixed(_))
+    }
+}
+
+impl From<&str> for Stat {
+    fn from(value: &str) -> Self {
+        let value = value.trim();
+        match value {
+            "X" => return Self::X,
+            "?" => return Self::Unknown,
+            "∞" => return Self::Infinite,
+            "*" => return Self::Variable(0),
+            _ => {}
+        }
+
+        if !value.starts_with('+')
+            && let Ok(v) = value.parse::<i32>()
+        {
+            return Self::Fixed(v);
+        }
+
+        let fixed = value
+            .strip_suffix("+*")
+            .or_else(|| value.strip_prefix("*+"))
+            .and_then(|v| v.parse::<i32>().ok());
+
+        match fixed {
+            Some(v) => Self::Variable(v),
+            None => Self::Other(value.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for Stat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fixed(v) => write!(f, "{v}"),
+            Self::Variable(0) => write!(f, "*"),
+            Self::Variable(v) => write!(f, "{v}+*"),
+            Self::X => write!(f, "X"),
+            Self::Unknown => write!(f, "?"),
+            Self::Infinite => write!(f, "∞"),
+            Self::Other(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+impl Type<Sqlite> for Stat {
+    fn type_info() -> SqliteTypeInfo {
+        <&str as Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for Stat {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        let value = <&str as Decode<Sqlite>>::decode(value)?;
+        Ok(Self::from(value))
+    }
+}
+
+#[derive(Debug, Clone, Default, FromRow)]
 pub struct Card {
     pub id: i32,
     pub card_id: String,
@@ -8,36 +103,41 @@ pub struct Card {
     pub name: String,
     pub color_indicator: Option<u8>,
     pub produced_mana: Option<u8>,
-    pub loyalty: Option<i32>,
+    #[sqlx(rename = "loyalty_text")]
+    pub loyalty: Option<Stat>,
     pub artist: Option<String>,
     pub oracle_id: Option<String>,
     pub type_line: Option<String>,
     pub lang: Option<String>,
     pub content_warning: bool,
     pub converted_mana_cost: f32,
-    pub image_status: Option<String>,
+    pub image_status: Option<ImageStatus>,
     pub flavor_text: Option<String>,
     pub arena_id: Option<i32>,
     pub illustration_id: Option<String>,
     pub oracle_text: Option<String>,
     pub colors: Option<u8>,
     pub color_identity: Option<u8>,
-    pub rarity: Option<String>,
-    pub power: Option<i32>,
-    pub toughness: Option<i32>,
+    pub rarity: Option<Rarity>,
+    #[sqlx(rename = "power_text")]
+    pub power: Option<Stat>,
+    #[sqlx(rename = "toughness_text")]
+    pub toughness: Option<Stat>,
+    #[sqlx(rename = "defense_text")]
+    pub defense: Option<Stat>,
     pub set_name: Option<String>,
     pub set_id: Option<String>,
-    pub set_type: Option<String>,
+    pub set_type: Option<SetType>,
     pub set_short: Option<String>,
     pub penny_rank: Option<String>,
     pub variation: bool,
     pub mtgo_id: Option<i32>,
     pub booster: bool,
-    pub border_color: Option<String>,
+    pub border_color: Option<BorderColor>,
     pub foil: bool,
     pub game_changer: bool,
     pub reprint: bool,
-    pub layout: Option<String>,
+    pub layout: Option<Layout>,
     pub reserved: bool,
     pub digital: bool,
     pub mana_cost: Option<String>,
@@ -45,4 +145,41 @@ pub struct Card {
     pub arena: bool,
     pub paper: bool,
     pub promo: bool,
+    pub nonfoil: bool,
+    pub highres_image: bool,
+    pub collector_number: Option<String>,
+    pub released_at: Option<String>,
+    pub edhrec_rank: Option<i32>,
+    pub frame: Option<String>,
+    pub security_stamp: Option<String>,
+    pub watermark: Option<String>,
+}
+
+#[cfg(test)]
+mod card_tests {
+    use super::*;
+
+    #[test]
+    fn parses_stats() {
+        assert_eq!(Stat::from("3"), Stat::Fixed(3));
+        assert_eq!(Stat::from("-1"), Stat::Fixed(-1));
+        assert_eq!(Stat::from("*"), Stat::Variable(0));
+        assert_eq!(Stat::from("1+*"), Stat::Variable(1));
+        assert_eq!(Stat::from("*+1"), Stat::Variable(1));
+        assert_eq!(Stat::from("X"), Stat::X);
+        assert_eq!(Stat::from("?"), Stat::Unknown);
+        assert_eq!(Stat::from("∞"), Stat::Infinite);
+        assert_eq!(Stat::from("+1"), Stat::Other("+1".to_string()));
+        assert_eq!(Stat::from("1.5"), Stat::Other("1.5".to_string()));
+    }
+
+    #[test]
+    fn stat_values() {
+        assert_eq!(Stat::from("1+*").value(), Some(1));
+        assert_eq!(Stat::from("*").value(), Some(0));
+        assert_eq!(Stat::from("∞").value(), Some(i32::MAX));
+        assert_eq!(Stat::from("X").value(), None);
+        assert!(!Stat::from("4").is_variable());
+        assert!(Stat::from("*").is_variable());
+    }
 }
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use sqlx::FromRow;
+
+use crate::{SqliteStore, card::Card, scryfall::Finish};
+
+/// Owned copies of a printing to write with [`SqliteStore::add_to_collection`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectionRecord {
+    pub card_id: String,
+    pub finish: Finish,
+    /// As the importing site writes it, e.g. `near_mint` or `Near Mint`
+    pub condition: Option<String>,
+    pub language: Option<String>,
+    /// Binder or box the copies are kept in
+    pub location: Option<String>,
+    pub quantity: u32,
+}
+
+impl CollectionRecord {
+    pub fn new(card_id: impl Into<String>, quantity: u32) -> Self {
+        Self {
+            card_id: card_id.into(),
+            finish: Finish::Nonfoil,
+            condition: None,
+            language: None,
+            location: None,
+            quantity,
+        }
+    }
+
+    pub fn with_finish(mut self, finish: Finish) -> Self {
+        self.finish = finish;
+        self
+    }
+
+    pub fn with_location(mut self, location: impl Into<String>) -> Self {
+        self.location = Some(location.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct CollectionEntry {
+    #[sqlx(flatten)]
+    pub card: Card,
+    pub finish: Finish,
+    pub condition: Option<String>,
+    pub language: Option<String>,
+    pub location: Option<String>,
+    pub quantity: u32,
+}
+
+impl SqliteStore {
+    /// Add owned copies, on top of any already in the collection
+    pub async fn add_to_collection(&self, records: &[CollectionRecord]) -> Result<()> {
+        let mut txn = self.pool.begin().await?;
+        for record in records {
+            let card_id = &record.card_id;
+            sqlx::query(
+                r#"
+                insert into collection_entry(card_id, finish, condition, language, location, quantity)
+                values(?, ?, ?, ?, ?, ?)
+                on conflict(card_id, finish, condition, language, location)
+                do update set quantity = quantity + excluded.quantity
+                "#,
+            )
+            .bind(card_id)
+            .bind(&record.finish)
+            .bind(record.condition.as_deref().unwrap_or_default())
+            .bind(record.language.as_deref().unwrap_or_default())
+            .bind(record.location.as_deref().unwrap_or_default())
+            .bind(record.quantity)
+            .execute(txn.as_mut())
+            .await
+            .with_context(|| format!("adding {card_id} to the collection"))?;
+        }
+
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// Take copies out of the collection, dropping the row once none are left
+    pub async fn remove_from_collection(&self, record: &CollectionRecord) -> Result<()> {
+        let card_id = &record.card_id;
+        let mut txn = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            update collection_entry set quantity = quantity - ?
+            where card_id = ? and finish = ? and condition = ? and language = ? and location = ?
+            "#,
+        )
+        .bind(record.quantity)
+        .bind(card_id)
+        .bind(&record.finish)
+        .bind(record.condition.as_deref().unwrap_or_default())
+        .bind(record.language.as_deref().unwrap_or_default())
+        .bind(record.location.as_deref().unwrap_or_default())
+        .execute(txn.as_mut())
+        .await
+        .with_context(|| format!("removing {card_id} from the collection"))?;
+
+        sqlx::query("delete from collection_entry where quantity <= 0")
+            .execute(txn.as_mut())
+            .await
+            .context("clearing empty collection entries")?;
+
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// Every owned printing, by name then location
+    pub async fn load_collection(&self) -> Result<Vec<CollectionEntry>> {
+        // Card faces share a Scryfall id so only join the front face
+        sqlx::query_as(
+            r#"
+            select
+                c.*,
+                e.finish,
+                nullif(e.condition, '') as condition,
+                nullif(e.language, '') as language,
+                nullif(e.location, '') as location,
+                e.quantity
+            from collection_entry e
+            join card c on c.id = (select min(id) from card where card_id = e.card_id)
+            order by c.name, e.location, c.set_short
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("loading the collection")
+    }
+
+    /// Owned copies of each card across every printing, keyed by name
+    pub async fn owned_cards(&self) -> Result<HashMap<String, u32>> {
+        let rows: Vec<(String, u32)> = sqlx::query_as(
+            r#"
+            select c.name, sum(e.quantity)
+            from collection_entry e
+            join card c on c.id = (select min(id) from card where card_id = e.card_id)
+            group by c.name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("counting owned cards")?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Decks playing each card and how many copies, keyed by name. Maybeboards are left out as
+    /// they don't take a copy away from the collection.
+    pub async fn cards_in_decks(
+        &self,
+        exclude_deck: Option<i64>,
+    ) -> Result<HashMap<String, Vec<(String, u32)>>> {
+        let rows: Vec<(String, String, u32)> = sqlx::query_as(
+            r#"
+            select c.name, d.name, sum(e.quantity)
+            from deck_entry e
+            join deck d on d.id = e.deck_id
+            join card c on c.id = (select min(id) from card where card_id = e.card_id)
+            where e.zone != 'maybeboard' and e.deck_id is not ?
+            group by c.name, d.id
+            order by d.name
+            "#,
+        )
+        .bind(exclude_deck)
+        .fetch_all(&self.pool)
+        .await
+        .context("fetching cards played in decks")?;
+
+        let mut decks: HashMap<String, Vec<(String, u32)>> = HashMap::new();
+        for (card, deck, quantity) in rows {
+            decks.entry(card).or_default().push((deck, quantity));
+        }
+
+        Ok(decks)
+    }
+}
+
+#[cfg(test)]
+mod collection_tests {
+    use super::*;
+    use crate::{
+        deck::{EntryRecord, Zone},
+        scryfall::Format,
+    };
+
+    #[tokio::test]
+    async fn collection() -> Result<()> {
+        let store = SqliteStore::in_memory().await?;
+        store.insert_test_card("m10-bolt", "Lightning Bolt").await?;
+        store.insert_test_card("2xm-bolt", "Lightning Bolt").await?;
+        store.insert_test_card("sol", "Sol Ring").await?;
+
+        store
+            .add_to_collection(&[
+                CollectionRecord::new("m10-bolt", 2).with_location("Binder"),
+                CollectionRecord::new("m10-bolt", 1).with_location("Binder"),
+                CollectionRecord::new("2xm-bolt", 1).with_finish(Finish::Foil),
+                CollectionRecord::new("sol", 1),
+            ])
+            .await?;
+
+        let collection = store.load_collection().await?;
+        assert_eq!(collection.len(), 3);
+        assert_eq!(collection[1].card.card_id, "m10-bolt");
+        assert_eq!(collection[1].quantity, 3);
+        assert_eq!(collection[1].location.as_deref(), Some("Binder"));
+        assert_eq!(collection[0].finish, Finish::Foil);
+        assert_eq!(collection[0].location, None);
+
+        let owned = store.owned_cards().await?;
+        assert_eq!(owned["Lightning Bolt"], 4);
+        assert_eq!(owned["Sol Ring"], 1);
+
+        store
+            .remove_from_collection(&CollectionRecord::new("sol", 1))
+            .await?;
+        store
+            .remove_from_collection(&CollectionRecord::new("m10-bolt", 1).with_location("Binder"))
+            .await?;
+        let owned = store.owned_cards().await?;
+        assert_eq!(owned.get("Sol Ring"), None);
+        assert_eq!(owned["Lightning Bolt"], 3);
+
+        let burn = store.create_deck("Burn", &Format::Modern).await?;
+        store
+            .save_deck(
+                burn,
+                &[
+                    EntryRecord::new("m10-bolt", Zone::Main, 4),
+                    EntryRecord::new("sol", Zone::Maybeboard, 1),
+                ],
+                None,
+            )
+            .await?;
+        let cube = store.create_deck("Cube", &Format::Vintage).await?;
+        store
+            .save_deck(cube, &[EntryRecord::new("2xm-bolt", Zone::Main, 1)], None)
+            .await?;
+
+        let in_decks = store.cards_in_decks(None).await?;
+        assert_eq!(
+            in_decks["Lightning Bolt"],
+            vec![("Burn".to_string(), 4), ("Cube".to_string(), 1)]
+        );
+        assert_eq!(in_decks.get("Sol Ring"), None);
+        let in_decks = store.cards_in_decks(Some(burn)).await?;
+        assert_eq!(in_decks["Lightning Bolt"], vec![("Cube".to_string(), 1)]);
+
+        Ok(())
+    }
+}
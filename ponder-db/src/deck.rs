@@ -0,0 +1,615 @@
+use crate::{
+    SqliteStore,
+    card::Card,
+    revision::record_revision,
+    scryfall::{Finish, Format},
+};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Zone {
+    Main,
+    Sideboard,
+    Commander,
+    /// Cards being considered for the deck
+    Maybeboard,
+    Companion,
+    /// Oathbreaker signature spells
+    SignatureSpell,
+    Attractions,
+    Contraptions,
+    /// Cards outside the game for wishes and similar effects
+    Wishboard,
+}
+
+impl Zone {
+    pub const ALL: [Zone; 9] = [
+        Zone::Commander,
+        Zone::SignatureSpell,
+        Zone::Companion,
+        Zone::Main,
+        Zone::Sideboard,
+        Zone::Wishboard,
+        Zone::Attractions,
+        Zone::Contraptions,
+        Zone::Maybeboard,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Main => "Main",
+            Self::Sideboard => "Sideboard",
+            Self::Commander => "Commander",
+            Self::Maybeboard => "Maybeboard",
+            Self::Companion => "Companion",
+            Self::SignatureSpell => "Signature Spell",
+            Self::Attractions => "Attractions",
+            Self::Contraptions => "Contraptions",
+            Self::Wishboard => "Wishboard",
+        }
+    }
+
+    /// Zones that count towards the deck size
+    pub fn is_deck(&self) -> bool {
+        matches!(self, Self::Main | Self::Commander | Self::SignatureSpell)
+    }
+
+    /// Everything but the maybeboard is brought to a game
+    pub fn is_played(&self) -> bool {
+        *self != Self::Maybeboard
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct DeckSummary {
+    pub id: i64,
+    pub name: String,
+    pub format: Format,
+    pub main_count: i64,
+    pub sideboard_count: i64,
+    pub commander_count: i64,
+}
+
+#[derive(Debug, FromRow)]
+pub struct StoredEntry {
+    #[sqlx(flatten)]
+    pub card: Card,
+    pub zone: Zone,
+    pub quantity: u32,
+    pub finish: Finish,
+    #[sqlx(skip)]
+    pub tags: Vec<String>,
+}
+
+/// An entry to write with [`SqliteStore::save_deck`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryRecord {
+    pub card_id: String,
+    pub zone: Zone,
+    pub quantity: u32,
+    pub finish: Finish,
+    /// User categories such as `ramp` or `removal`
+    pub tags: Vec<String>,
+}
+
+impl EntryRecord {
+    pub fn new(card_id: impl Into<String>, zone: Zone, quantity: u32) -> Self {
+        Self {
+            card_id: card_id.into(),
+            zone,
+            quantity,
+            finish: Finish::Nonfoil,
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn with_finish(mut self, finish: Finish) -> Self {
+        self.finish = finish;
+        self
+    }
+
+    pub fn with_tags(mut self, tags: &[&str]) -> Self {
+        self.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct StoredDeck {
+    pub id: i64,
+    pub name: String,
+    pub format: Format,
+    pub entries: Vec<StoredEntry>,
+}
+
+impl SqliteStore {
+    pub async fn create_deck(&self, name: &str, format: &Format) -> Result<i64> {
+        let mut txn = self.pool.begin().await?;
+
+        // Formats are normally added on update but decks can be made before that
+        sqlx::query("insert or ignore into format(name) values(?)")
+            .bind(format.to_string())
+            .execute(txn.as_mut())
+            .await
+            .with_context(|| format!("inserting {format:?} into db"))?;
+
+        let id: i64 = sqlx::query_scalar(
+            "insert into deck(name, format_id) select ?, id from format where name = ? returning id",
+        )
+        .bind(name)
+        .bind(format.to_string())
+        .fetch_one(txn.as_mut())
+        .await
+        .with_context(|| format!("creating deck - {name}"))?;
+
+        txn.commit().await?;
+        Ok(id)
+    }
+
+    pub async fn rename_deck(&self, deck_id: i64, name: &str) -> Result<()> {
+        sqlx::query("update deck set name = ? where id = ?")
+            .bind(name)
+            .bind(deck_id)
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("renaming deck {deck_id} - {name}"))?;
+
+        Ok(())
+    }
+
+    pub async fn delete_deck(&self, deck_id: i64) -> Result<()> {
+        let mut txn = self.pool.begin().await?;
+        for table in ["deck_revision_entry_tag", "deck_revision_entry"] {
+            sqlx::query(&format!(
+                "delete from {table} where revision_id in (select id from deck_revision where deck_id = ?)"
+            ))
+            .bind(deck_id)
+            .execute(txn.as_mut())
+            .await
+            .with_context(|| format!("deleting {table} for deck {deck_id}"))?;
+        }
+
+        sqlx::query("delete from deck_revision where deck_id = ?")
+            .bind(deck_id)
+            .execute(txn.as_mut())
+            .await
+            .with_context(|| format!("deleting revisions for deck {deck_id}"))?;
+
+        sqlx::query("delete from deck_entry_tag where deck_id = ?")
+            .bind(deck_id)
+            .execute(txn.as_mut())
+            .await
+            .with_context(|| format!("deleting tags for deck {deck_id}"))?;
+
+        sqlx::query("delete from deck_entry where deck_id = ?")
+            .bind(deck_id)
+            .execute(txn.as_mut())
+            .await
+            .with_context(|| format!("deleting entries for deck {deck_id}"))?;
+
+        sqlx::query("delete from deck where id = ?")
+            .bind(deck_id)
+            .execute(txn.as_mut())
+            .await
+            .with_context(|| format!("deleting deck {deck_id}"))?;
+
+        txn.commit().await?;
+        Ok(())
+    }
+
+    pub async fn list_decks(&self) -> Result<Vec<DeckSummary>> {
+        let query = r#"
+            select
+                d.id,
+                d.name,
+                f.name as format,
+                coalesce(sum(case when e.zone = 'main' then e.quantity end), 0) as main_count,
+                coalesce(sum(case when e.zone = 'sideboard' then e.quantity end), 0) as sideboard_count,
+                coalesce(sum(case when e.zone = 'commander' then e.quantity end), 0) as commander_count
+            from deck d
+            join format f on f.id = d.format_id
+            left join deck_entry e on e.deck_id = d.id
+            group by d.id
+            order by d.name
+        "#;
+
+        let decks: Vec<DeckSummary> = sqlx::query_as(query)
+            .fetch_all(&self.pool)
+            .await
+            .context("listing decks")?;
+
+        Ok(decks)
+    }
+
+    pub async fn load_deck(&self, deck_id: i64) -> Result<StoredDeck> {
+        let (name, format): (String, Format) = sqlx::query_as(
+            "select d.name, f.name from deck d join format f on f.id = d.format_id where d.id = ?",
+        )
+        .bind(deck_id)
+        .fetch_one(&self.pool)
+        .await
+        .with_context(|| format!("fetching deck {deck_id}"))?;
+
+        // Card faces share a Scryfall id so only join the front face
+        let mut entries: Vec<StoredEntry> = sqlx::query_as(
+            r#"
+            select c.*, e.zone, e.quantity, e.finish
+            from deck_entry e
+            join card c on c.id = (select min(id) from card where card_id = e.card_id)
+            where e.deck_id = ?
+            order by e.zone, c.name, e.finish
+            "#,
+        )
+        .bind(deck_id)
+        .fetch_all(&self.pool)
+        .await
+        .with_context(|| format!("fetching entries for deck {deck_id} - {name}"))?;
+
+        let tags: Vec<(String, Zone, String)> = sqlx::query_as(
+            "select card_id, zone, tag from deck_entry_tag where deck_id = ? order by tag",
+        )
+        .bind(deck_id)
+        .fetch_all(&self.pool)
+        .await
+        .with_context(|| format!("fetching tags for deck {deck_id} - {name}"))?;
+
+        // Every finish of a card in a zone shares its tags
+        for (card_id, zone, tag) in tags {
+            for entry in entries
+                .iter_mut()
+                .filter(|entry| entry.card.card_id == card_id && entry.zone == zone)
+            {
+                entry.tags.push(tag.clone());
+            }
+        }
+
+        Ok(StoredDeck {
+            id: deck_id,
+            name,
+            format,
+            entries,
+        })
+    }
+
+    pub async fn load_decks(&self) -> Result<Vec<StoredDeck>> {
+        let mut decks = Vec::new();
+        for summary in self.list_decks().await? {
+            decks.push(self.load_deck(summary.id).await?);
+        }
+
+        Ok(decks)
+    }
+
+    /// Add copies of a card, on top of any already in the deck in the same finish
+    pub async fn add_entry(
+        &self,
+        deck_id: i64,
+        card_id: &str,
+        zone: Zone,
+        finish: &Finish,
+        quantity: u32,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            insert into deck_entry(deck_id, card_id, zone, finish, quantity) values(?, ?, ?, ?, ?)
+            on conflict(deck_id, card_id, zone, finish) do update set quantity = quantity + excluded.quantity
+            "#,
+        )
+        .bind(deck_id)
+        .bind(card_id)
+        .bind(zone)
+        .bind(finish)
+        .bind(quantity)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("adding {card_id} to deck {deck_id}"))?;
+
+        Ok(())
+    }
+
+    /// Remove a card in one finish, dropping its tags once no finish of it is left in the zone
+    pub async fn remove_entry(
+        &self,
+        deck_id: i64,
+        card_id: &str,
+        zone: Zone,
+        finish: &Finish,
+    ) -> Result<()> {
+        let mut txn = self.pool.begin().await?;
+        sqlx::query(
+            "delete from deck_entry where deck_id = ? and card_id = ? and zone = ? and finish = ?",
+        )
+        .bind(deck_id)
+        .bind(card_id)
+        .bind(zone)
+        .bind(finish)
+        .execute(txn.as_mut())
+        .await
+        .with_context(|| format!("removing {card_id} from deck {deck_id}"))?;
+
+        sqlx::query(
+            r#"
+            delete from deck_entry_tag where deck_id = ? and card_id = ? and zone = ?
+            and not exists (
+                select 1 from deck_entry e
+                where e.deck_id = deck_entry_tag.deck_id
+                and e.card_id = deck_entry_tag.card_id
+                and e.zone = deck_entry_tag.zone
+            )
+            "#,
+        )
+        .bind(deck_id)
+        .bind(card_id)
+        .bind(zone)
+        .execute(txn.as_mut())
+        .await
+        .with_context(|| format!("removing tags of {card_id} from deck {deck_id}"))?;
+
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// Replace every entry in a deck and record the result as a revision, returning its id
+    pub async fn save_deck(
+        &self,
+        deck_id: i64,
+        entries: &[EntryRecord],
+        note: Option<&str>,
+    ) -> Result<i64> {
+        let mut txn = self.pool.begin().await?;
+        for table in ["deck_entry_tag", "deck_entry"] {
+            sqlx::query(&format!("delete from {table} where deck_id = ?"))
+                .bind(deck_id)
+                .execute(txn.as_mut())
+                .await
+                .with_context(|| format!("clearing {table} for deck {deck_id}"))?;
+        }
+
+        for entry in entries.iter() {
+            let card_id = &entry.card_id;
+            sqlx::query(
+                r#"
+                insert into deck_entry(deck_id, card_id, zone, quantity, finish) values(?, ?, ?, ?, ?)
+                on conflict(deck_id, card_id, zone, finish) do update set quantity = quantity + excluded.quantity
+                "#,
+            )
+            .bind(deck_id)
+            .bind(card_id)
+            .bind(entry.zone)
+            .bind(entry.quantity)
+            .bind(&entry.finish)
+            .execute(txn.as_mut())
+            .await
+            .with_context(|| format!("adding {card_id} to deck {deck_id}"))?;
+
+            for tag in entry.tags.iter() {
+                sqlx::query(
+                    "insert or ignore into deck_entry_tag(deck_id, card_id, zone, tag) values(?, ?, ?, ?)",
+                )
+                .bind(deck_id)
+                .bind(card_id)
+                .bind(entry.zone)
+                .bind(tag)
+                .execute(txn.as_mut())
+                .await
+                .with_context(|| format!("tagging {card_id} in deck {deck_id} - {tag}"))?;
+            }
+        }
+
+        let revision = record_revision(txn.as_mut(), deck_id, note).await?;
+        txn.commit().await?;
+        Ok(revision)
+    }
+
+    /// Tag a card in a zone of a deck, failing if the card isn't there
+    pub async fn add_tag(&self, deck_id: i64, card_id: &str, zone: Zone, tag: &str) -> Result<()> {
+        let exists: bool = sqlx::query_scalar(
+            "select exists(select 1 from deck_entry where deck_id = ? and card_id = ? and zone = ?)",
+        )
+        .bind(deck_id)
+        .bind(card_id)
+        .bind(zone)
+        .fetch_one(&self.pool)
+        .await
+        .with_context(|| format!("finding {card_id} in deck {deck_id}"))?;
+        if !exists {
+            bail!(
+                "{card_id} isn't in the {} zone of deck {deck_id}",
+                zone.label()
+            );
+        }
+
+        sqlx::query(
+            "insert or ignore into deck_entry_tag(deck_id, card_id, zone, tag) values(?, ?, ?, ?)",
+        )
+        .bind(deck_id)
+        .bind(card_id)
+        .bind(zone)
+        .bind(tag)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("tagging {card_id} in deck {deck_id} - {tag}"))?;
+
+        Ok(())
+    }
+
+    pub async fn remove_tag(
+        &self,
+        deck_id: i64,
+        card_id: &str,
+        zone: Zone,
+        tag: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            "delete from deck_entry_tag where deck_id = ? and card_id = ? and zone = ? and tag = ?",
+        )
+        .bind(deck_id)
+        .bind(card_id)
+        .bind(zone)
+        .bind(tag)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("untagging {card_id} in deck {deck_id} - {tag}"))?;
+
+        Ok(())
+    }
+
+    /// Every tag used in a deck with the number of cards carrying it, counting copies
+    pub async fn deck_tags(&self, deck_id: i64) -> Result<Vec<(String, i64)>> {
+        let tags: Vec<(String, i64)> = sqlx::query_as(
+            r#"
+            select t.tag, sum(e.quantity)
+            from deck_entry_tag t
+            join deck_entry e on e.deck_id = t.deck_id and e.card_id = t.card_id and e.zone = t.zone
+            where t.deck_id = ?
+            group by t.tag
+            order by t.tag
+            "#,
+        )
+        .bind(deck_id)
+        .fetch_all(&self.pool)
+        .await
+        .with_context(|| format!("fetching tags for deck {deck_id}"))?;
+
+        Ok(tags)
+    }
+
+    /// Set the number of copies of a card in a finish, removing it at zero
+    pub async fn set_quantity(
+        &self,
+        deck_id: i64,
+        card_id: &str,
+        zone: Zone,
+        finish: &Finish,
+        quantity: u32,
+    ) -> Result<()> {
+        if quantity == 0 {
+            return self.remove_entry(deck_id, card_id, zone, finish).await;
+        }
+
+        sqlx::query(
+            r#"
+            insert into deck_entry(deck_id, card_id, zone, finish, quantity) values(?, ?, ?, ?, ?)
+            on conflict(deck_id, card_id, zone, finish) do update set quantity = excluded.quantity
+            "#,
+        )
+        .bind(deck_id)
+        .bind(card_id)
+        .bind(zone)
+        .bind(finish)
+        .bind(quantity)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("setting quantity of {card_id} in deck {deck_id}"))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod deck_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn deck_crud() -> Result<()> {
+        let store = SqliteStore::in_memory().await?;
+        store.insert_test_card("bolt", "Lightning Bolt").await?;
+        store.insert_test_card("goyf", "Tarmogoyf").await?;
+        store.insert_test_card("kari", "Kari Zev").await?;
+
+        let id = store.create_deck("Burn", &Format::Modern).await?;
+        store
+            .add_entry(id, "bolt", Zone::Main, &Finish::Nonfoil, 3)
+            .await?;
+        store
+            .add_entry(id, "bolt", Zone::Main, &Finish::Nonfoil, 1)
+            .await?;
+        store
+            .add_entry(id, "bolt", Zone::Main, &Finish::Foil, 1)
+            .await?;
+        store
+            .add_entry(id, "goyf", Zone::Sideboard, &Finish::Nonfoil, 2)
+            .await?;
+        store
+            .add_entry(id, "kari", Zone::Main, &Finish::Nonfoil, 4)
+            .await?;
+        store
+            .set_quantity(id, "kari", Zone::Main, &Finish::Nonfoil, 0)
+            .await?;
+        store.rename_deck(id, "Boros Burn").await?;
+
+        let summaries = store.list_decks().await?;
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].name, "Boros Burn");
+        assert_eq!(summaries[0].format, Format::Modern);
+        assert_eq!(summaries[0].main_count, 5);
+        assert_eq!(summaries[0].sideboard_count, 2);
+
+        let deck = store.load_deck(id).await?;
+        let entries = deck
+            .entries
+            .iter()
+            .map(|e| (e.card.name.as_str(), e.zone, e.finish.clone(), e.quantity))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![
+                ("Lightning Bolt", Zone::Main, Finish::Foil, 1),
+                ("Lightning Bolt", Zone::Main, Finish::Nonfoil, 4),
+                ("Tarmogoyf", Zone::Sideboard, Finish::Nonfoil, 2)
+            ]
+        );
+
+        store
+            .save_deck(
+                id,
+                &[
+                    EntryRecord::new("kari", Zone::Commander, 1).with_tags(&["aggro"]),
+                    EntryRecord::new("goyf", Zone::Maybeboard, 1),
+                    EntryRecord::new("bolt", Zone::Main, 4).with_tags(&["removal", "aggro"]),
+                    EntryRecord::new("bolt", Zone::Main, 1).with_finish(Finish::Foil),
+                ],
+                None,
+            )
+            .await?;
+        store
+            .add_tag(id, "goyf", Zone::Maybeboard, "beater")
+            .await?;
+        store.remove_tag(id, "bolt", Zone::Main, "aggro").await?;
+        assert!(
+            store
+                .add_tag(id, "goyf", Zone::Main, "beater")
+                .await
+                .is_err()
+        );
+
+        let deck = store.load_deck(id).await?;
+        assert_eq!(deck.entries.len(), 4);
+        assert_eq!(deck.entries[0].card.name, "Kari Zev");
+        assert_eq!(deck.entries[0].tags, vec!["aggro"]);
+        // Both finishes of the bolts share their tags
+        assert_eq!(deck.entries[1].finish, Finish::Foil);
+        assert_eq!(deck.entries[1].tags, vec!["removal"]);
+        assert_eq!(deck.entries[2].tags, vec!["removal"]);
+        assert_eq!(deck.entries[3].zone, Zone::Maybeboard);
+        assert_eq!(
+            store.deck_tags(id).await?,
+            vec![
+                ("aggro".to_string(), 1),
+                ("beater".to_string(), 1),
+                ("removal".to_string(), 5)
+            ]
+        );
+
+        store.delete_deck(id).await?;
+        assert!(store.list_decks().await?.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use crate::scryfall::{Layout, ScryfallCard, SetType};
+use serde::{Deserialize, Serialize};
+
+/// Decides which Scryfall cards make it into the database.
+///
+/// The default skips the things ponder can't do anything useful with: vanguard and playtest
+/// cards, art cards, tokens and cards from the 90s Sega / Astral games.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IngestFilter {
+    pub excluded_set_types: Vec<SetType>,
+    /// Skip cards printed for any of these games, e.g. `sega`
+    pub excluded_games: Vec<String>,
+    pub excluded_layouts: Vec<Layout>,
+    /// Only keep cards in these languages, everything is kept when empty
+    pub languages: Vec<String>,
+    pub include_digital: bool,
+    /// Skip cards whose set name contains any of these
+    pub excluded_set_names: Vec<String>,
+    /// Skip cards whose type line contains any of these
+    pub excluded_type_lines: Vec<String>,
+    /// Skip cards whose name contains any of these
+    pub excluded_names: Vec<String>,
+}
+
+impl Default for IngestFilter {
+    fn default() -> Self {
+        Self {
+            excluded_set_types: vec![SetType::Vanguard],
+            excluded_games: vec!["sega".to_string(), "astral".to_string()],
+            excluded_layouts: vec![],
+            languages: vec![],
+            include_digital: true,
+            excluded_set_names: vec!["Mystery Booster Playtest".to_string()],
+            // Art cards and tokens
+            excluded_type_lines: vec!["Card".to_string(), "Token".to_string()],
+            excluded_names: vec![],
+        }
+    }
+}
+
+impl IngestFilter {
+    pub fn allows(&self, card: &ScryfallCard) -> bool {
+        let contains_any = |value: Option<&str>, patterns: &[String]| match value {
+            Some(value) => patterns.iter().any(|p| value.contains(p.as_str())),
+            None => false,
+        };
+
+        if let Some(ref st) = card.set_type
+            && self.excluded_set_types.contains(st)
+        {
+            return false;
+        }
+
+        if let Some(ref layout) = card.layout
+            && self.excluded_layouts.contains(layout)
+        {
+            return false;
+        }
+
+        if let Some(ref games) = card.games
+            && games
+                .iter()
+                .any(|g| self.excluded_games.iter().any(|e| e == g))
+        {
+            return false;
+        }
+
+        if !self.languages.is_empty() {
+            match card.lang {
+                Some(ref lang) if self.languages.iter().any(|l| l == lang) => {}
+                _ => return false,
+            }
+        }
+
+        if !self.include_digital && card.digital == Some(true) {
+            return false;
+        }
+
+        if contains_any(card.set_name.as_deref(), &self.excluded_set_names)
+            || contains_any(card.type_line.as_deref(), &self.excluded_type_lines)
+            || contains_any(card.name.as_deref(), &self.excluded_names)
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod filter_tests {
+    use super::*;
+
+    fn card(json: &str) -> ScryfallCard<'_> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn default_filter() {
+        let filter = IngestFilter::default();
+        assert!(filter.allows(&card(
+            r#"{"name": "Lightning Bolt", "type_line": "Instant", "set_type": "core", "games": ["paper", "mtgo"]}"#
+        )));
+        assert!(!filter.allows(&card(
+            r#"{"name": "Goblin", "type_line": "Token Creature — Goblin"}"#
+        )));
+        assert!(!filter.allows(&card(r#"{"name": "Karn", "set_type": "vanguard"}"#)));
+        assert!(!filter.allows(&card(
+            r#"{"name": "Whatever", "set_name": "Mystery Booster Playtest Cards 2019"}"#
+        )));
+        assert!(!filter.allows(&card(r#"{"name": "Sega Card", "games": ["sega"]}"#)));
+    }
+
+    #[test]
+    fn configured_filter() {
+        let filter: IngestFilter = serde_json::from_str(
+            r#"{"excluded_set_names": [], "include_digital": false, "languages": ["en"], "excluded_set_types": ["funny"]}"#,
+        )
+        .unwrap();
+
+        assert!(filter.allows(&card(
+            r#"{"name": "Whatever", "lang": "en", "set_name": "Mystery Booster Playtest Cards 2019"}"#
+        )));
+        assert!(!filter.allows(&card(
+            r#"{"name": "Ice Cauldron", "lang": "en", "digital": true}"#
+        )));
+        assert!(!filter.allows(&card(r#"{"name": "Blitz Hellion", "lang": "de"}"#)));
+        assert!(!filter.allows(&card(
+            r#"{"name": "Ow", "lang": "en", "set_type": "funny"}"#
+        )));
+        // Fields missing from the config keep their defaults
+        assert!(!filter.allows(&card(
+            r#"{"name": "Goblin", "lang": "en", "type_line": "Token Creature — Goblin"}"#
+        )));
+    }
+}
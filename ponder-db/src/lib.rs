@@ -1,16 +1,31 @@
 use anyhow::{Context, Result};
 use card::Card;
-use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions};
+use scryfall::{Format, Legality, Rarity};
+use sqlx::{
+    QueryBuilder, Sqlite,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions},
+};
 
 use std::{
+    collections::HashMap,
     path::{Path, PathBuf},
     str::FromStr,
 };
 
 pub mod card;
+pub mod collection;
+pub mod deck;
+pub mod filter;
+pub mod price;
+pub mod recommend;
+pub mod revision;
 pub mod scryfall;
+pub mod search;
+pub mod similar;
 mod updater;
 
+use filter::IngestFilter;
+use search::CardSearch;
 use updater::DatabaseUpdater;
 
 #[derive(Debug, Clone)]
@@ -51,8 +66,11 @@ impl SqliteStore {
         DatabaseUpdater::new(&self.pool).update().await
     }
 
-    pub async fn load_decks(&self) -> Result<Vec<()>> {
-        Ok(vec![])
+    pub async fn update_with_filter(&self, filter: IngestFilter) -> Result<()> {
+        DatabaseUpdater::new(&self.pool)
+            .with_filter(filter)
+            .update()
+            .await
     }
 
     pub async fn query_card_by_name<'a>(&self, name: &str) -> Result<Vec<Card>> {
@@ -65,4 +83,482 @@ impl SqliteStore {
 
         Ok(results)
     }
+
+    /// Fetch the newest printing of a card by its exact name, ignoring case.
+    ///
+    /// Multi-faced cards can be named by their front face or in full, e.g. `Fire // Ice`.
+    pub async fn card_by_name(&self, name: &str) -> Result<Option<Card>> {
+        let front = name.split(" // ").next().unwrap_or(name).trim();
+        let card: Option<Card> = sqlx::query_as(
+            r#"
+            select * from card c
+            where c.name = ? collate nocase
+            and c.id = (select min(id) from card where card_id = c.card_id)
+            order by c.released_at desc, c.id
+            limit 1
+            "#,
+        )
+        .bind(front)
+        .fetch_optional(&self.pool)
+        .await
+        .with_context(|| format!("fetching card by exact name - {name}"))?;
+
+        Ok(card)
+    }
+
+    /// Fetch a card by its exact name from a particular set, for lists without collector numbers
+    pub async fn card_by_name_in_set(&self, name: &str, set: &str) -> Result<Option<Card>> {
+        let front = name.split(" // ").next().unwrap_or(name).trim();
+        let card: Option<Card> = sqlx::query_as(
+            r#"
+            select * from card c
+            where c.name = ? collate nocase and c.set_short = ?
+            and c.id = (select min(id) from card where card_id = c.card_id)
+            order by c.id
+            limit 1
+            "#,
+        )
+        .bind(front)
+        .bind(set.to_lowercase())
+        .fetch_optional(&self.pool)
+        .await
+        .with_context(|| format!("fetching card by name and set - {name} ({set})"))?;
+
+        Ok(card)
+    }
+
+    /// Every card name, to pass to [`search::suggest_card_names`] for names that don't match
+    pub async fn card_names(&self) -> Result<Vec<String>> {
+        sqlx::query_scalar("select distinct name from card")
+            .fetch_all(&self.pool)
+            .await
+            .context("fetching card names")
+    }
+
+    /// Fetch a printing by its set code and collector number, e.g. `("M10", "146")`.
+    ///
+    /// Multi-faced cards return their front face.
+    pub async fn card_by_set_and_number(&self, set: &str, number: &str) -> Result<Option<Card>> {
+        // Set codes are stored lowercase, as Scryfall has them, so this can use the set index
+        let card: Option<Card> = sqlx::query_as(
+            "select * from card where set_short = ? and collector_number = ? order by id limit 1",
+        )
+        .bind(set.to_lowercase())
+        .bind(number)
+        .fetch_optional(&self.pool)
+        .await
+        .with_context(|| format!("fetching card by set and number - {set} {number}"))?;
+
+        Ok(card)
+    }
+
+    pub async fn card_by_arena_id(&self, arena_id: i32) -> Result<Option<Card>> {
+        let card: Option<Card> =
+            sqlx::query_as("select * from card where arena_id = ? order by id limit 1")
+                .bind(arena_id)
+                .fetch_optional(&self.pool)
+                .await
+                .with_context(|| format!("fetching card by arena id - {arena_id}"))?;
+
+        Ok(card)
+    }
+
+    pub async fn card_by_mtgo_id(&self, mtgo_id: i32) -> Result<Option<Card>> {
+        let card: Option<Card> =
+            sqlx::query_as("select * from card where mtgo_id = ? order by id limit 1")
+                .bind(mtgo_id)
+                .fetch_optional(&self.pool)
+                .await
+                .with_context(|| format!("fetching card by mtgo id - {mtgo_id}"))?;
+
+        Ok(card)
+    }
+
+    pub async fn card_by_scryfall_id(&self, scryfall_id: &str) -> Result<Option<Card>> {
+        let card: Option<Card> =
+            sqlx::query_as("select * from card where card_id = ? order by id limit 1")
+                .bind(scryfall_id)
+                .fetch_optional(&self.pool)
+                .await
+                .with_context(|| format!("fetching card by scryfall id - {scryfall_id}"))?;
+
+        Ok(card)
+    }
+
+    /// Every printing of a card, newest first. Multi-faced cards return their front face.
+    pub async fn cards_by_oracle_id(&self, oracle_id: &str) -> Result<Vec<Card>> {
+        let results: Vec<Card> = sqlx::query_as(
+            r#"
+            select * from card c
+            where c.oracle_id = ?
+            and c.id = (select min(id) from card where card_id = c.card_id)
+            order by c.released_at desc, c.id
+            "#,
+        )
+        .bind(oracle_id)
+        .fetch_all(&self.pool)
+        .await
+        .with_context(|| format!("fetching cards by oracle id - {oracle_id}"))?;
+
+        Ok(results)
+    }
+
+    /// Every rarity a card has been printed at
+    pub async fn printed_rarities(&self, name: &str) -> Result<Vec<Rarity>> {
+        let rarities: Vec<Rarity> = sqlx::query_scalar(
+            "select distinct rarity from card where name = ? and rarity is not null",
+        )
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await
+        .with_context(|| format!("fetching printed rarities - {name}"))?;
+
+        Ok(rarities)
+    }
+
+    /// Legality of each card in a format, keyed by `Card::id`
+    pub async fn legalities(
+        &self,
+        card_ids: &[i32],
+        format: &Format,
+    ) -> Result<HashMap<i32, Legality>> {
+        if card_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut qb = QueryBuilder::<Sqlite>::new(
+            "select cast(l.card_id as integer), l.status from legality l join format f on f.id = l.format_id where f.name = ",
+        );
+        qb.push_bind(format.to_string()).push(" and l.card_id in (");
+        let mut ids = qb.separated(", ");
+        for id in card_ids {
+            ids.push_bind(*id);
+        }
+        ids.push_unseparated(")");
+
+        let rows: Vec<(i32, Legality)> = qb
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| format!("fetching {format} legalities"))?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Card types of each card, e.g. `Creature` and `Artifact`, keyed by `Card::id`
+    pub async fn card_types(&self, card_ids: &[i32]) -> Result<HashMap<i32, Vec<String>>> {
+        self.values_by_card(
+            card_ids,
+            "select cast(card_id as integer), type from card_type where card_id in (",
+        )
+        .await
+        .context("fetching card types")
+    }
+
+    /// Keywords of each card, e.g. `Flying`, keyed by `Card::id`
+    pub async fn card_keywords(&self, card_ids: &[i32]) -> Result<HashMap<i32, Vec<String>>> {
+        self.values_by_card(
+            card_ids,
+            "select cast(ck.card_id as integer), k.name from card_keywords ck join keyword k on k.id = ck.keyword_id where ck.card_id in (",
+        )
+        .await
+        .context("fetching card keywords")
+    }
+
+    /// Run a `select card_id, value` query ending in an open `in (` over a list of card ids
+    async fn values_by_card(
+        &self,
+        card_ids: &[i32],
+        query: &str,
+    ) -> Result<HashMap<i32, Vec<String>>> {
+        let mut values: HashMap<i32, Vec<String>> = HashMap::new();
+        if card_ids.is_empty() {
+            return Ok(values);
+        }
+
+        let mut qb = QueryBuilder::<Sqlite>::new(query);
+        let mut ids = qb.separated(", ");
+        for id in card_ids {
+            ids.push_bind(*id);
+        }
+        ids.push_unseparated(")");
+
+        let rows: Vec<(i32, String)> = qb.build_query_as().fetch_all(&self.pool).await?;
+        for (id, value) in rows {
+            values.entry(id).or_default().push(value);
+        }
+
+        Ok(values)
+    }
+
+    pub async fn search(&self, search: &CardSearch) -> Result<Vec<Card>> {
+        let results: Vec<Card> = search
+            .build()
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| format!("searching cards - {search:?}"))?;
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+impl SqliteStore {
+    pub(crate) async fn in_memory() -> Result<Self> {
+        let connect_opts = SqliteConnectOptions::from_str("sqlite::memory:")?;
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(connect_opts)
+            .await
+            .context("creating in-memory database")?;
+
+        Self::setup_db(&pool).await?;
+        Ok(Self { pool })
+    }
+
+    pub(crate) async fn insert_test_card(&self, card_id: &str, name: &str) -> Result<i64> {
+        let id = sqlx::query_scalar(
+            "insert into card(card_id, object, name, content_warning, converted_mana_cost, variation, booster, foil, game_changer, reprint, reserved, digital, mtgo, arena, paper, promo) values(?, 'card', ?, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0) returning id",
+        )
+        .bind(card_id)
+        .bind(name)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    pub(crate) async fn insert_test_legality(
+        &self,
+        card_id: i64,
+        format: &Format,
+        legality: &Legality,
+    ) -> Result<()> {
+        sqlx::query("insert or ignore into format(name) values(?)")
+            .bind(format.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("insert into legality(card_id, format_id, status) select ?, id, ? from format where name = ?")
+            .bind(card_id)
+            .bind(legality.to_string())
+            .bind(format.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod store_tests {
+    use super::*;
+    use crate::{
+        card::Stat,
+        search::{StatFilter, suggest_card_names},
+    };
+
+    async fn set_power(store: &SqliteStore, id: i64, power: &str) -> Result<()> {
+        let stat = Stat::from(power);
+        sqlx::query("update card set power = ?, power_text = ? where id = ?")
+            .bind(stat.value())
+            .bind(power)
+            .bind(id)
+            .execute(&store.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn search_variable_stats() -> Result<()> {
+        let store = SqliteStore::in_memory().await?;
+        let goyf = store.insert_test_card("a", "Tarmogoyf").await?;
+        set_power(&store, goyf, "*").await?;
+        let bear = store.insert_test_card("b", "Grizzly Bears").await?;
+        set_power(&store, bear, "2").await?;
+        let lord = store.insert_test_card("c", "Lord of Extinction").await?;
+        set_power(&store, lord, "1+*").await?;
+
+        let cards = store
+            .search(&CardSearch::new().power(StatFilter::Variable))
+            .await?;
+        let names = cards.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Lord of Extinction", "Tarmogoyf"]);
+        assert_eq!(cards[1].power, Some(Stat::Variable(0)));
+
+        sqlx::query("update card set rarity = 'mythic' where id = ?")
+            .bind(goyf)
+            .execute(&store.pool)
+            .await?;
+
+        let cards = store
+            .search(&CardSearch::new().rarity(Rarity::Mythic))
+            .await?;
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].rarity, Some(Rarity::Mythic));
+
+        let cards = store
+            .search(&CardSearch::new().power(StatFilter::AtLeast(1)))
+            .await?;
+        let names = cards.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Grizzly Bears", "Lord of Extinction"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn lookup_printings() -> Result<()> {
+        let store = SqliteStore::in_memory().await?;
+        for (card_id, name, set, number, released) in [
+            ("a", "Lightning Bolt", "m10", "146", "2009-07-17"),
+            ("b", "Lightning Bolt", "2xm", "141", "2020-08-07"),
+            // Stand-in back face, to check printings aren't listed once per face
+            ("b", "Lightning Bolt Back", "2xm", "141", "2020-08-07"),
+        ] {
+            let id = store.insert_test_card(card_id, name).await?;
+            sqlx::query("update card set set_short = ?, collector_number = ?, released_at = ?, oracle_id = 'bolt', arena_id = ? where id = ?")
+                .bind(set)
+                .bind(number)
+                .bind(released)
+                .bind(id as i32 + 1000)
+                .bind(id)
+                .execute(&store.pool)
+                .await?;
+        }
+
+        let card = store.card_by_set_and_number("M10", "146").await?.unwrap();
+        assert_eq!(card.card_id, "a");
+        assert!(store.card_by_set_and_number("M10", "147").await?.is_none());
+
+        let card = store.card_by_arena_id(1002).await?.unwrap();
+        assert_eq!(card.card_id, "b");
+        assert_eq!(store.card_by_scryfall_id("b").await?.unwrap().id, 2);
+
+        let card = store.card_by_name("lightning bolt").await?.unwrap();
+        assert_eq!(card.card_id, "b");
+        let card = store
+            .card_by_name_in_set("Lightning Bolt", "M10")
+            .await?
+            .unwrap();
+        assert_eq!(card.card_id, "a");
+        let names = store.card_names().await?;
+        let suggestions = suggest_card_names(&names, "Lightning Blot", 3);
+        assert_eq!(suggestions, vec!["Lightning Bolt"]);
+        assert!(suggest_card_names(&names, "Counterspell", 3).is_empty());
+
+        let printings = store.cards_by_oracle_id("bolt").await?;
+        let ids = printings
+            .iter()
+            .map(|c| c.card_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["b", "a"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn card_legalities() -> Result<()> {
+        let store = SqliteStore::in_memory().await?;
+        let bolt = store.insert_test_card("bolt", "Lightning Bolt").await?;
+        let ritual = store.insert_test_card("ritual", "Dark Ritual").await?;
+        store
+            .insert_test_legality(bolt, &Format::Modern, &Legality::Legal)
+            .await?;
+        store
+            .insert_test_legality(ritual, &Format::Modern, &Legality::NotLegal)
+            .await?;
+        store
+            .insert_test_legality(ritual, &Format::Vintage, &Legality::Legal)
+            .await?;
+
+        let legalities = store
+            .legalities(&[bolt as i32, ritual as i32], &Format::Modern)
+            .await?;
+        assert_eq!(legalities.len(), 2);
+        assert_eq!(legalities[&(bolt as i32)], Legality::Legal);
+        assert_eq!(legalities[&(ritual as i32)], Legality::NotLegal);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn search_mana_lands() -> Result<()> {
+        let store = SqliteStore::in_memory().await?;
+        for (card_id, name, produced, reserved) in [
+            ("tundra", "Tundra", 3, true),
+            ("fountain", "Hallowed Fountain", 3, false),
+            ("foundry", "Sacred Foundry", 9, false),
+            ("tower", "Command Tower", 31, false),
+        ] {
+            let id = store.insert_test_card(card_id, name).await?;
+            sqlx::query(
+                "update card set type_line = 'Land', produced_mana = ?, reserved = ? where id = ?",
+            )
+            .bind(produced)
+            .bind(reserved)
+            .bind(id)
+            .execute(&store.pool)
+            .await?;
+            store
+                .insert_test_legality(id, &Format::Legacy, &Legality::Legal)
+                .await?;
+        }
+
+        let names = |cards: Vec<Card>| cards.into_iter().map(|c| c.name).collect::<Vec<_>>();
+        let lands = CardSearch::new()
+            .type_line("Land")
+            .legal_in(Format::Legacy)
+            .produces_within(3);
+        assert_eq!(
+            names(store.search(&lands).await?),
+            vec!["Hallowed Fountain", "Tundra"]
+        );
+        assert_eq!(
+            names(store.search(&lands.exclude_reserved()).await?),
+            vec!["Hallowed Fountain"]
+        );
+        assert!(
+            store
+                .search(&CardSearch::new().legal_in(Format::Modern))
+                .await?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn types_and_keywords() -> Result<()> {
+        let store = SqliteStore::in_memory().await?;
+        let arbor = store.insert_test_card("arbor", "Dryad Arbor").await?;
+        let bolt = store.insert_test_card("bolt", "Lightning Bolt").await?;
+        for card_type in ["Land", "Creature"] {
+            sqlx::query("insert into card_type(card_id, type) values(?, ?)")
+                .bind(arbor)
+                .bind(card_type)
+                .execute(&store.pool)
+                .await?;
+        }
+        sqlx::query("insert into keyword(id, name) values(1, 'Flying')")
+            .execute(&store.pool)
+            .await?;
+        sqlx::query("insert into card_keywords(card_id, keyword_id) values(?, 1)")
+            .bind(arbor)
+            .execute(&store.pool)
+            .await?;
+
+        let ids = [arbor as i32, bolt as i32];
+        let mut types = store.card_types(&ids).await?;
+        types.values_mut().for_each(|types| types.sort());
+        assert_eq!(types.len(), 1);
+        assert_eq!(types[&(arbor as i32)], vec!["Creature", "Land"]);
+
+        let keywords = store.card_keywords(&ids).await?;
+        assert_eq!(keywords[&(arbor as i32)], vec!["Flying"]);
+        assert!(store.card_keywords(&[]).await?.is_empty());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use sqlx::{FromRow, QueryBuilder, Sqlite};
+
+use crate::{
+    SqliteStore,
+    card::Card,
+    scryfall::{Finish, Format, Legality},
+};
+
+/// Latest Scryfall prices of a printing
+#[derive(Debug, Clone, Copy, Default, PartialEq, FromRow)]
+pub struct Prices {
+    pub usd: Option<f64>,
+    pub usd_foil: Option<f64>,
+    pub usd_etched: Option<f64>,
+    pub eur: Option<f64>,
+    pub eur_foil: Option<f64>,
+    pub tix: Option<f64>,
+}
+
+impl Prices {
+    /// Dollar price of the cheapest finish
+    pub fn usd_any(&self) -> Option<f64> {
+        [self.usd, self.usd_foil, self.usd_etched]
+            .into_iter()
+            .flatten()
+            .min_by(f64::total_cmp)
+    }
+
+    /// Dollar price in the given finish, or of the cheapest finish when that one isn't priced
+    pub fn usd_for(&self, finish: &Finish) -> Option<f64> {
+        let price = match finish {
+            Finish::Nonfoil => self.usd,
+            Finish::Foil => self.usd_foil,
+            Finish::Etched => self.usd_etched,
+            Finish::Unknown(_) => None,
+        };
+        price.or_else(|| self.usd_any())
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct PricedCard {
+    #[sqlx(flatten)]
+    pub card: Card,
+    #[sqlx(flatten)]
+    pub prices: Prices,
+}
+
+#[derive(Debug, FromRow)]
+struct PriceRow {
+    card_id: String,
+    #[sqlx(flatten)]
+    prices: Prices,
+}
+
+impl SqliteStore {
+    /// Prices of each printing, keyed by Scryfall id
+    pub async fn prices(&self, card_ids: &[&str]) -> Result<HashMap<String, Prices>> {
+        if card_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut qb = QueryBuilder::<Sqlite>::new(
+            "select card_id, usd, usd_foil, usd_etched, eur, eur_foil, tix from card_price where card_id in (",
+        );
+        let mut ids = qb.separated(", ");
+        for id in card_ids {
+            ids.push_bind(*id);
+        }
+        ids.push_unseparated(")");
+
+        let rows: Vec<PriceRow> = qb
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await
+            .context("fetching prices")?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.card_id, row.prices))
+            .collect())
+    }
+
+    /// Cheapest printing in dollars of each card legal in a format, keyed by name
+    pub async fn cheapest_printings(
+        &self,
+        names: &[&str],
+        format: &Format,
+    ) -> Result<HashMap<String, PricedCard>> {
+        let mut cheapest: HashMap<String, PricedCard> = HashMap::new();
+        if names.is_empty() {
+            return Ok(cheapest);
+        }
+
+        let mut qb = QueryBuilder::<Sqlite>::new(
+            r#"
+            select c.*, p.usd, p.usd_foil, p.usd_etched, p.eur, p.eur_foil, p.tix
+            from card c
+            join card_price p on p.card_id = c.card_id
+            where c.id in (
+                select cast(l.card_id as integer)
+                from legality l join format f on f.id = l.format_id
+                where f.name = "#,
+        );
+        qb.push_bind(format.to_string())
+            .push(" and l.status in (")
+            .push_bind(Legality::Legal.to_string())
+            .push(", ")
+            .push_bind(Legality::Restricted.to_string())
+            .push(")) and c.name in (");
+        let mut binds = qb.separated(", ");
+        for name in names {
+            binds.push_bind(*name);
+        }
+        binds.push_unseparated(")");
+
+        let printings: Vec<PricedCard> = qb
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await
+            .with_context(|| format!("fetching {format} printing prices"))?;
+
+        for printing in printings {
+            let Some(price) = printing.prices.usd_any() else {
+                continue;
+            };
+
+            let cheaper = cheapest
+                .get(&printing.card.name)
+                .and_then(|current| current.prices.usd_any())
+                .is_none_or(|current| price < current);
+            if cheaper {
+                cheapest.insert(printing.card.name.clone(), printing);
+            }
+        }
+
+        Ok(cheapest)
+    }
+}
+
+#[cfg(test)]
+mod price_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cheapest_printings() -> Result<()> {
+        let store = SqliteStore::in_memory().await?;
+        for (card_id, name, usd, foil, legal) in [
+            ("beta", "Lightning Bolt", Some(500.0), None, false),
+            ("m10", "Lightning Bolt", Some(2.5), Some(8.0), true),
+            ("2xm", "Lightning Bolt", None, Some(1.75), true),
+            ("goyf", "Tarmogoyf", Some(12.0), None, true),
+        ] {
+            let id = store.insert_test_card(card_id, name).await?;
+            sqlx::query("insert into card_price(card_id, usd, usd_foil) values(?, ?, ?)")
+                .bind(card_id)
+                .bind(usd)
+                .bind(foil)
+                .execute(&store.pool)
+                .await?;
+            let legality = if legal {
+                Legality::Legal
+            } else {
+                Legality::NotLegal
+            };
+            store
+                .insert_test_legality(id, &Format::Modern, &legality)
+                .await?;
+        }
+
+        let prices = store.prices(&["m10", "unknown"]).await?;
+        assert_eq!(prices.len(), 1);
+        assert_eq!(prices["m10"].usd_any(), Some(2.5));
+
+        let cheapest = store
+            .cheapest_printings(&["Lightning Bolt", "Tarmogoyf"], &Format::Modern)
+            .await?;
+        assert_eq!(cheapest["Lightning Bolt"].card.card_id, "2xm");
+        assert_eq!(cheapest["Lightning Bolt"].prices.usd_any(), Some(1.75));
+        assert_eq!(cheapest["Tarmogoyf"].prices.usd, Some(12.0));
+        assert!(
+            store
+                .cheapest_printings(&["Lightning Bolt"], &Format::Legacy)
+                .await?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+}
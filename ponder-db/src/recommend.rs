@@ -0,0 +1,179 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+
+use crate::SqliteStore;
+
+/// Which stored decks play each card, to find cards that turn up together
+#[derive(Debug, Clone, Default)]
+pub struct CooccurrenceIndex {
+    decks: HashMap<i64, HashSet<String>>,
+    cards: HashMap<String, HashSet<i64>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recommendation {
+    pub name: String,
+    /// Share of the decks playing a seed card that also play this one, averaged over the seeds
+    pub inclusion: f64,
+    /// Inclusion less the share of all decks playing the card, so staples score near zero
+    pub synergy: f64,
+}
+
+impl CooccurrenceIndex {
+    /// Build the index from `(deck id, card name)` pairs
+    pub fn new(entries: impl IntoIterator<Item = (i64, String)>) -> Self {
+        let mut index = Self::default();
+        for (deck_id, name) in entries {
+            index.cards.entry(name.clone()).or_default().insert(deck_id);
+            index.decks.entry(deck_id).or_default().insert(name);
+        }
+
+        index
+    }
+
+    pub fn deck_count(&self) -> usize {
+        self.decks.len()
+    }
+
+    /// Cards most often played alongside the seed cards, best synergy first.
+    ///
+    /// The excluded deck, usually the one the seeds came from, is left out of every count.
+    pub fn recommend(
+        &self,
+        seeds: &[&str],
+        exclude_deck: Option<i64>,
+        limit: usize,
+    ) -> Vec<Recommendation> {
+        let included = |deck_id: &&i64| Some(**deck_id) != exclude_deck;
+        let total = self.decks.keys().filter(included).count();
+        let seeds = seeds.iter().copied().collect::<HashSet<_>>();
+
+        let mut inclusion: HashMap<&str, f64> = HashMap::new();
+        let mut seeded = 0;
+        for seed in &seeds {
+            let Some(decks) = self.cards.get(*seed) else {
+                continue;
+            };
+            let decks = decks.iter().filter(included).collect::<Vec<_>>();
+            if decks.is_empty() {
+                continue;
+            }
+
+            seeded += 1;
+            for deck_id in &decks {
+                for name in &self.decks[*deck_id] {
+                    if !seeds.contains(name.as_str()) {
+                        *inclusion.entry(name).or_default() += 1.0 / decks.len() as f64;
+                    }
+                }
+            }
+        }
+
+        if seeded == 0 {
+            return Vec::new();
+        }
+
+        let mut recommendations = inclusion
+            .into_iter()
+            .map(|(name, share)| {
+                let inclusion = share / seeded as f64;
+                let played = self.cards[name].iter().filter(included).count();
+                Recommendation {
+                    name: name.to_string(),
+                    inclusion,
+                    synergy: inclusion - played as f64 / total as f64,
+                }
+            })
+            .filter(|recommendation| recommendation.synergy > 0.0)
+            .collect::<Vec<_>>();
+
+        recommendations.sort_by(|a, b| {
+            b.synergy
+                .total_cmp(&a.synergy)
+                .then(b.inclusion.total_cmp(&a.inclusion))
+                .then(a.name.cmp(&b.name))
+        });
+        recommendations.truncate(limit);
+        recommendations
+    }
+}
+
+impl SqliteStore {
+    /// Index the cards played in every stored deck, leaving out sideboards and maybeboards
+    pub async fn cooccurrence_index(&self) -> Result<CooccurrenceIndex> {
+        let rows: Vec<(i64, String)> = sqlx::query_as(
+            r#"
+            select distinct e.deck_id, c.name
+            from deck_entry e
+            join card c on c.id = (select min(id) from card where card_id = e.card_id)
+            where e.zone not in ('sideboard', 'maybeboard', 'wishboard')
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("indexing cards played together")?;
+
+        Ok(CooccurrenceIndex::new(rows))
+    }
+}
+
+#[cfg(test)]
+mod recommend_tests {
+    use super::*;
+    use crate::{
+        deck::{EntryRecord, Zone},
+        scryfall::Format,
+    };
+
+    #[tokio::test]
+    async fn recommend_from_decks() -> Result<()> {
+        let store = SqliteStore::in_memory().await?;
+        for (card_id, name) in [
+            ("krenko", "Krenko, Mob Boss"),
+            ("warren", "Goblin Warchief"),
+            ("sol", "Sol Ring"),
+            ("wrath", "Wrath of God"),
+            ("bolt", "Lightning Bolt"),
+        ] {
+            store.insert_test_card(card_id, name).await?;
+        }
+
+        for (name, cards) in [
+            ("Goblins", vec!["krenko", "warren", "sol"]),
+            ("More Goblins", vec!["krenko", "warren", "sol", "bolt"]),
+            ("Control", vec!["wrath", "sol"]),
+            ("Burn", vec!["bolt", "sol"]),
+        ] {
+            let id = store.create_deck(name, &Format::Commander).await?;
+            let mut entries = cards
+                .iter()
+                .map(|card| EntryRecord::new(*card, Zone::Main, 1))
+                .collect::<Vec<_>>();
+            entries.push(EntryRecord::new("wrath", Zone::Maybeboard, 1));
+            store.save_deck(id, &entries, None).await?;
+        }
+
+        let index = store.cooccurrence_index().await?;
+        assert_eq!(index.deck_count(), 4);
+
+        let recommendations = index.recommend(&["Krenko, Mob Boss"], None, 10);
+        let names = recommendations
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect::<Vec<_>>();
+        // Sol Ring is in every deck and Lightning Bolt in half, as with Krenko, so neither has
+        // any synergy
+        assert_eq!(names, vec!["Goblin Warchief"]);
+        assert_eq!(recommendations[0].inclusion, 1.0);
+        assert_eq!(recommendations[0].synergy, 0.5);
+
+        // Leaving out the deck the seeds come from
+        let recommendations =
+            index.recommend(&["Krenko, Mob Boss", "Goblin Warchief"], Some(2), 10);
+        assert!(recommendations.is_empty());
+        assert!(index.recommend(&["Black Lotus"], None, 10).is_empty());
+
+        Ok(())
+    }
+}
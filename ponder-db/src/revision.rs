@@ -0,0 +1,449 @@
+use std::collections::BTreeMap;
+
+use crate::{SqliteStore, deck::Zone, scryfall::Finish};
+use anyhow::{Context, Result};
+use sqlx::{FromRow, SqliteConnection};
+
+/// A snapshot of a deck's entries, taken every time the deck is saved
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct Revision {
+    pub id: i64,
+    pub deck_id: i64,
+    /// UTC, formatted as `YYYY-MM-DD HH:MM:SS`
+    pub created_at: String,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct RevisionEntry {
+    pub card_id: String,
+    pub name: String,
+    pub zone: Zone,
+    pub finish: Finish,
+    pub quantity: u32,
+    #[sqlx(skip)]
+    pub tags: Vec<String>,
+}
+
+/// How many copies of a card in a finish were in a zone before and after, and the tags it gained
+/// or lost
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryChange {
+    pub card_id: String,
+    pub name: String,
+    pub zone: Zone,
+    pub finish: Finish,
+    pub before: u32,
+    pub after: u32,
+    pub tags_added: Vec<String>,
+    pub tags_removed: Vec<String>,
+}
+
+impl EntryChange {
+    pub fn is_added(&self) -> bool {
+        self.before == 0
+    }
+
+    pub fn is_removed(&self) -> bool {
+        self.after == 0
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RevisionDiff {
+    /// Ordered by zone then card name
+    pub changes: Vec<EntryChange>,
+}
+
+impl RevisionDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn added(&self) -> impl Iterator<Item = &EntryChange> {
+        self.changes.iter().filter(|change| change.is_added())
+    }
+
+    pub fn removed(&self) -> impl Iterator<Item = &EntryChange> {
+        self.changes.iter().filter(|change| change.is_removed())
+    }
+
+    /// Cards still in the zone with a different number of copies or different tags
+    pub fn changed(&self) -> impl Iterator<Item = &EntryChange> {
+        self.changes
+            .iter()
+            .filter(|change| !change.is_added() && !change.is_removed())
+    }
+}
+
+/// Snapshot the current entries of a deck as a new revision
+pub(crate) async fn record_revision(
+    conn: &mut SqliteConnection,
+    deck_id: i64,
+    note: Option<&str>,
+) -> Result<i64> {
+    let id: i64 =
+        sqlx::query_scalar("insert into deck_revision(deck_id, note) values(?, ?) returning id")
+            .bind(deck_id)
+            .bind(note)
+            .fetch_one(&mut *conn)
+            .await
+            .with_context(|| format!("creating revision for deck {deck_id}"))?;
+
+    sqlx::query(
+        "insert into deck_revision_entry(revision_id, card_id, zone, quantity, finish) select ?, card_id, zone, quantity, finish from deck_entry where deck_id = ?",
+    )
+    .bind(id)
+    .bind(deck_id)
+    .execute(&mut *conn)
+    .await
+    .with_context(|| format!("copying entries into revision {id}"))?;
+
+    sqlx::query(
+        "insert into deck_revision_entry_tag(revision_id, card_id, zone, tag) select ?, card_id, zone, tag from deck_entry_tag where deck_id = ?",
+    )
+    .bind(id)
+    .bind(deck_id)
+    .execute(&mut *conn)
+    .await
+    .with_context(|| format!("copying tags into revision {id}"))?;
+
+    Ok(id)
+}
+
+/// Insert the entries and tags of a revision into a deck
+async fn copy_revision(conn: &mut SqliteConnection, revision_id: i64, deck_id: i64) -> Result<()> {
+    sqlx::query(
+        "insert into deck_entry(deck_id, card_id, zone, quantity, finish) select ?, card_id, zone, quantity, finish from deck_revision_entry where revision_id = ?",
+    )
+    .bind(deck_id)
+    .bind(revision_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "insert into deck_entry_tag(deck_id, card_id, zone, tag) select ?, card_id, zone, tag from deck_revision_entry_tag where revision_id = ?",
+    )
+    .bind(deck_id)
+    .bind(revision_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+impl SqliteStore {
+    /// Revisions of a deck, newest first
+    pub async fn list_revisions(&self, deck_id: i64) -> Result<Vec<Revision>> {
+        let revisions: Vec<Revision> =
+            sqlx::query_as("select * from deck_revision where deck_id = ? order by id desc")
+                .bind(deck_id)
+                .fetch_all(&self.pool)
+                .await
+                .with_context(|| format!("listing revisions for deck {deck_id}"))?;
+
+        Ok(revisions)
+    }
+
+    /// The latest revision saved at or before a UTC timestamp, e.g. `2025-07-11 18:00:00`, or by
+    /// the end of a day given just its date
+    pub async fn revision_at(&self, deck_id: i64, timestamp: &str) -> Result<Option<Revision>> {
+        // Timestamps compare as text, so a bare date would sort before that day's revisions
+        let end_of_day;
+        let timestamp = if timestamp.contains(' ') {
+            timestamp
+        } else {
+            end_of_day = format!("{timestamp} 23:59:59");
+            &end_of_day
+        };
+
+        let revision: Option<Revision> = sqlx::query_as(
+            "select * from deck_revision where deck_id = ? and created_at <= ? order by id desc limit 1",
+        )
+        .bind(deck_id)
+        .bind(timestamp)
+        .fetch_optional(&self.pool)
+        .await
+        .with_context(|| format!("fetching revision of deck {deck_id} at {timestamp}"))?;
+
+        Ok(revision)
+    }
+
+    pub async fn revision_entries(&self, revision_id: i64) -> Result<Vec<RevisionEntry>> {
+        let mut entries: Vec<RevisionEntry> = sqlx::query_as(
+            r#"
+            select e.card_id, coalesce(c.name, e.card_id) as name, e.zone, e.finish, e.quantity
+            from deck_revision_entry e
+            left join card c on c.id = (select min(id) from card where card_id = e.card_id)
+            where e.revision_id = ?
+            order by e.zone, name, e.finish
+            "#,
+        )
+        .bind(revision_id)
+        .fetch_all(&self.pool)
+        .await
+        .with_context(|| format!("fetching entries for revision {revision_id}"))?;
+
+        let tags: Vec<(String, Zone, String)> = sqlx::query_as(
+            "select card_id, zone, tag from deck_revision_entry_tag where revision_id = ? order by tag",
+        )
+        .bind(revision_id)
+        .fetch_all(&self.pool)
+        .await
+        .with_context(|| format!("fetching tags for revision {revision_id}"))?;
+
+        // Every finish of a card in a zone shares its tags
+        for (card_id, zone, tag) in tags {
+            for entry in entries
+                .iter_mut()
+                .filter(|entry| entry.card_id == card_id && entry.zone == zone)
+            {
+                entry.tags.push(tag.clone());
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Cards added, removed, changed in quantity or retagged going from one revision to another
+    pub async fn diff_revisions(&self, from: i64, to: i64) -> Result<RevisionDiff> {
+        type Side = (u32, Vec<String>);
+        let mut entries: BTreeMap<(Zone, String, Finish, String), (Side, Side)> = BTreeMap::new();
+        for entry in self.revision_entries(from).await? {
+            entries
+                .entry((entry.zone, entry.name, entry.finish, entry.card_id))
+                .or_default()
+                .0 = (entry.quantity, entry.tags);
+        }
+
+        for entry in self.revision_entries(to).await? {
+            entries
+                .entry((entry.zone, entry.name, entry.finish, entry.card_id))
+                .or_default()
+                .1 = (entry.quantity, entry.tags);
+        }
+
+        let changes = entries
+            .into_iter()
+            .filter(|(_, (before, after))| before != after)
+            .map(
+                |((zone, name, finish, card_id), ((before, old_tags), (after, new_tags)))| {
+                    EntryChange {
+                        card_id,
+                        name,
+                        zone,
+                        finish,
+                        before,
+                        after,
+                        tags_added: new_tags
+                            .iter()
+                            .filter(|tag| !old_tags.contains(tag))
+                            .cloned()
+                            .collect(),
+                        tags_removed: old_tags
+                            .iter()
+                            .filter(|tag| !new_tags.contains(tag))
+                            .cloned()
+                            .collect(),
+                    }
+                },
+            )
+            .collect();
+
+        Ok(RevisionDiff { changes })
+    }
+
+    /// Put a deck back to how it was at a revision, recording that as a new revision
+    pub async fn restore_revision(&self, revision_id: i64, note: Option<&str>) -> Result<i64> {
+        let mut txn = self.pool.begin().await?;
+        let deck_id: i64 = sqlx::query_scalar("select deck_id from deck_revision where id = ?")
+            .bind(revision_id)
+            .fetch_one(txn.as_mut())
+            .await
+            .with_context(|| format!("fetching revision {revision_id}"))?;
+
+        for table in ["deck_entry_tag", "deck_entry"] {
+            sqlx::query(&format!("delete from {table} where deck_id = ?"))
+                .bind(deck_id)
+                .execute(txn.as_mut())
+                .await
+                .with_context(|| format!("clearing {table} for deck {deck_id}"))?;
+        }
+
+        copy_revision(txn.as_mut(), revision_id, deck_id)
+            .await
+            .with_context(|| format!("restoring revision {revision_id} of deck {deck_id}"))?;
+
+        let note = note
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Restored revision {revision_id}"));
+        let id = record_revision(txn.as_mut(), deck_id, Some(&note)).await?;
+
+        txn.commit().await?;
+        Ok(id)
+    }
+
+    /// Start a new deck in the same format from an old revision, returning the new deck's id
+    pub async fn branch_revision(&self, revision_id: i64, name: &str) -> Result<i64> {
+        let mut txn = self.pool.begin().await?;
+        let deck_id: i64 = sqlx::query_scalar(
+            r#"
+            insert into deck(name, format_id)
+            select ?, d.format_id from deck_revision r join deck d on d.id = r.deck_id where r.id = ?
+            returning id
+            "#,
+        )
+        .bind(name)
+        .bind(revision_id)
+        .fetch_one(txn.as_mut())
+        .await
+        .with_context(|| format!("branching revision {revision_id} - {name}"))?;
+
+        copy_revision(txn.as_mut(), revision_id, deck_id)
+            .await
+            .with_context(|| format!("copying revision {revision_id} into deck {deck_id}"))?;
+
+        let note = format!("Branched from revision {revision_id}");
+        record_revision(txn.as_mut(), deck_id, Some(&note)).await?;
+
+        txn.commit().await?;
+        Ok(deck_id)
+    }
+}
+
+#[cfg(test)]
+mod revision_tests {
+    use super::*;
+    use crate::{deck::EntryRecord, scryfall::Format};
+
+    #[tokio::test]
+    async fn history() -> Result<()> {
+        let store = SqliteStore::in_memory().await?;
+        store.insert_test_card("bolt", "Lightning Bolt").await?;
+        store.insert_test_card("goyf", "Tarmogoyf").await?;
+        store.insert_test_card("guide", "Goblin Guide").await?;
+
+        let id = store.create_deck("Burn", &Format::Modern).await?;
+        let first = store
+            .save_deck(
+                id,
+                &[
+                    EntryRecord::new("bolt", Zone::Main, 4).with_tags(&["removal"]),
+                    EntryRecord::new("goyf", Zone::Main, 2),
+                ],
+                Some("first draft"),
+            )
+            .await?;
+        let second = store
+            .save_deck(
+                id,
+                &[
+                    EntryRecord::new("bolt", Zone::Main, 3).with_tags(&["burn"]),
+                    EntryRecord::new("guide", Zone::Main, 4),
+                ],
+                None,
+            )
+            .await?;
+
+        let revisions = store.list_revisions(id).await?;
+        assert_eq!(
+            revisions.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![second, first]
+        );
+        assert_eq!(revisions[1].note.as_deref(), Some("first draft"));
+        assert_eq!(
+            store.revision_at(id, "9999-01-01").await?.map(|r| r.id),
+            Some(second)
+        );
+        assert!(store.revision_at(id, "2000-01-01").await?.is_none());
+        let today = &revisions[0].created_at[..10];
+        assert_eq!(
+            store.revision_at(id, today).await?.map(|r| r.id),
+            Some(second)
+        );
+
+        let diff = store.diff_revisions(first, second).await?;
+        let summary = |changes: Vec<&EntryChange>| {
+            changes
+                .into_iter()
+                .map(|c| (c.name.clone(), c.before, c.after))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            summary(diff.added().collect()),
+            vec![("Goblin Guide".to_string(), 0, 4)]
+        );
+        assert_eq!(
+            summary(diff.removed().collect()),
+            vec![("Tarmogoyf".to_string(), 2, 0)]
+        );
+        assert_eq!(
+            summary(diff.changed().collect()),
+            vec![("Lightning Bolt".to_string(), 4, 3)]
+        );
+        let bolt = diff.changed().next().unwrap();
+        assert_eq!(bolt.tags_added, vec!["burn"]);
+        assert_eq!(bolt.tags_removed, vec!["removal"]);
+
+        let restored = store.restore_revision(first, None).await?;
+        assert!(store.diff_revisions(first, restored).await?.is_empty());
+        let deck = store.load_deck(id).await?;
+        assert_eq!(deck.entries.len(), 2);
+        assert_eq!(deck.entries[0].tags, vec!["removal"]);
+        assert_eq!(store.deck_tags(id).await?, vec![("removal".to_string(), 4)]);
+
+        let branch = store.branch_revision(second, "Burn v2").await?;
+        let deck = store.load_deck(branch).await?;
+        assert_eq!(deck.format, Format::Modern);
+        assert_eq!(deck.entries.len(), 2);
+        assert_eq!(deck.entries[1].tags, vec!["burn"]);
+        assert_eq!(store.list_revisions(branch).await?.len(), 1);
+
+        // Retagging without changing quantities still shows up in the diff
+        let branched = store.list_revisions(branch).await?[0].id;
+        let retagged = store
+            .save_deck(
+                branch,
+                &[
+                    EntryRecord::new("bolt", Zone::Main, 3).with_tags(&["burn"]),
+                    EntryRecord::new("guide", Zone::Main, 4).with_tags(&["aggro"]),
+                ],
+                None,
+            )
+            .await?;
+        let diff = store.diff_revisions(branched, retagged).await?;
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(diff.changes[0].name, "Goblin Guide");
+        assert_eq!(diff.changes[0].tags_added, vec!["aggro"]);
+        assert_eq!(diff.changed().count(), 1);
+
+        // Swapping a card for a foil copy is a removal and an addition
+        let foiled = store
+            .save_deck(
+                branch,
+                &[
+                    EntryRecord::new("bolt", Zone::Main, 3)
+                        .with_tags(&["burn"])
+                        .with_finish(Finish::Foil),
+                    EntryRecord::new("guide", Zone::Main, 4).with_tags(&["aggro"]),
+                ],
+                None,
+            )
+            .await?;
+        let diff = store.diff_revisions(retagged, foiled).await?;
+        let finishes = diff
+            .changes
+            .iter()
+            .map(|c| (c.finish.clone(), c.before, c.after))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            finishes,
+            vec![(Finish::Nonfoil, 3, 0), (Finish::Foil, 0, 3)]
+        );
+
+        store.delete_deck(id).await?;
+        assert!(store.list_revisions(id).await?.is_empty());
+
+        Ok(())
+    }
+}
@@ -1,6 +1,13 @@
+use crate::filter::IngestFilter;
 use anyhow::Result;
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::{
+    Decode, Encode, Sqlite, Type,
+    encode::IsNull,
+    error::BoxDynError,
+    sqlite::{SqliteArgumentValue, SqliteTypeInfo, SqliteValueRef},
+};
 use std::{borrow::Cow, collections::BTreeMap, path::PathBuf};
 
 const URL: &str = "https://api.scryfall.com/bulk-data";
@@ -26,6 +33,17 @@ pub(crate) struct ImageUris<'a> {
     pub(crate) border_crop: Option<Cow<'a, str>>,
 }
 
+/// Prices as Scryfall sends them, decimal strings that are null when there's no price
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub(crate) struct ScryfallPrices<'a> {
+    pub(crate) usd: Option<Cow<'a, str>>,
+    pub(crate) usd_foil: Option<Cow<'a, str>>,
+    pub(crate) usd_etched: Option<Cow<'a, str>>,
+    pub(crate) eur: Option<Cow<'a, str>>,
+    pub(crate) eur_foil: Option<Cow<'a, str>>,
+    pub(crate) tix: Option<Cow<'a, str>>,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "lowercase")]
 pub enum Format {
@@ -60,6 +78,36 @@ impl std::fmt::Display for Format {
     }
 }
 
+impl std::str::FromStr for Format {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(s.to_lowercase()))
+    }
+}
+
+// Stored as their `Display` text
+macro_rules! sqlx_from_str {
+    ($($name:ident),* $(,)?) => {
+        $(
+            impl Type<Sqlite> for $name {
+                fn type_info() -> SqliteTypeInfo {
+                    <&str as Type<Sqlite>>::type_info()
+                }
+            }
+
+            impl<'r> Decode<'r, Sqlite> for $name {
+                fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+                    let value = <&str as Decode<Sqlite>>::decode(value)?;
+                    Ok(value.parse()?)
+                }
+            }
+        )*
+    };
+}
+
+sqlx_from_str!(Format, Legality);
+
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "snake_case")]
 pub enum Legality {
@@ -76,6 +124,14 @@ impl std::fmt::Display for Legality {
     }
 }
 
+impl std::str::FromStr for Legality {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(s.to_string()))
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Color {
     Colorless = 0,
@@ -102,6 +158,183 @@ impl From<char> for Color {
     }
 }
 
+impl Color {
+    pub const WUBRG: [Color; 5] = [
+        Color::White,
+        Color::Blue,
+        Color::Black,
+        Color::Red,
+        Color::Green,
+    ];
+
+    /// Colors set in a bitmask as stored in the database, in WUBRG order
+    pub fn from_bits(bits: u8) -> Vec<Color> {
+        Self::WUBRG
+            .into_iter()
+            .filter(|c| bits & *c as u8 != 0)
+            .collect()
+    }
+}
+
+// Scryfall string values with a fallback so new values don't break ingest
+macro_rules! scryfall_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident => $value:literal),* $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub enum $name {
+            $($variant,)*
+            Unknown(String),
+        }
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $value,)*
+                    Self::Unknown(value) => value,
+                }
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                match value {
+                    $($value => Self::$variant,)*
+                    _ => Self::Unknown(value.to_string()),
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.as_str())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = Cow::<'de, str>::deserialize(deserializer)?;
+                Ok(Self::from(value.as_ref()))
+            }
+        }
+
+        impl Type<Sqlite> for $name {
+            fn type_info() -> SqliteTypeInfo {
+                <&str as Type<Sqlite>>::type_info()
+            }
+        }
+
+        impl<'r> Decode<'r, Sqlite> for $name {
+            fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+                let value = <&str as Decode<Sqlite>>::decode(value)?;
+                Ok(Self::from(value))
+            }
+        }
+
+        impl<'q> Encode<'q, Sqlite> for $name {
+            fn encode_by_ref(
+                &self,
+                buf: &mut Vec<SqliteArgumentValue<'q>>,
+            ) -> Result<IsNull, BoxDynError> {
+                <String as Encode<Sqlite>>::encode(self.as_str().to_string(), buf)
+            }
+        }
+    };
+}
+
+scryfall_enum!(
+    /// Declared from least to most rare so sorting follows the usual order
+    Rarity {
+        Common => "common",
+        Uncommon => "uncommon",
+        Rare => "rare",
+        Mythic => "mythic",
+        Special => "special",
+        Bonus => "bonus",
+    }
+);
+
+scryfall_enum!(Layout {
+    Normal => "normal",
+    Split => "split",
+    Flip => "flip",
+    Transform => "transform",
+    ModalDfc => "modal_dfc",
+    Meld => "meld",
+    Leveler => "leveler",
+    Class => "class",
+    Case => "case",
+    Saga => "saga",
+    Adventure => "adventure",
+    Mutate => "mutate",
+    Prototype => "prototype",
+    Battle => "battle",
+    Planar => "planar",
+    Scheme => "scheme",
+    Vanguard => "vanguard",
+    Token => "token",
+    DoubleFacedToken => "double_faced_token",
+    Emblem => "emblem",
+    Augment => "augment",
+    Host => "host",
+    ArtSeries => "art_series",
+    ReversibleCard => "reversible_card",
+});
+
+scryfall_enum!(BorderColor {
+    Black => "black",
+    White => "white",
+    Borderless => "borderless",
+    Yellow => "yellow",
+    Silver => "silver",
+    Gold => "gold",
+});
+
+scryfall_enum!(SetType {
+    Core => "core",
+    Expansion => "expansion",
+    Masters => "masters",
+    Eternal => "eternal",
+    Alchemy => "alchemy",
+    Masterpiece => "masterpiece",
+    Arsenal => "arsenal",
+    FromTheVault => "from_the_vault",
+    Spellbook => "spellbook",
+    PremiumDeck => "premium_deck",
+    DuelDeck => "duel_deck",
+    DraftInnovation => "draft_innovation",
+    TreasureChest => "treasure_chest",
+    Commander => "commander",
+    Planechase => "planechase",
+    Archenemy => "archenemy",
+    Vanguard => "vanguard",
+    Funny => "funny",
+    Starter => "starter",
+    Box => "box",
+    Promo => "promo",
+    Token => "token",
+    Memorabilia => "memorabilia",
+    Minigame => "minigame",
+});
+
+scryfall_enum!(ImageStatus {
+    Missing => "missing",
+    Placeholder => "placeholder",
+    Lowres => "lowres",
+    HighresScan => "highres_scan",
+});
+
+scryfall_enum!(Finish {
+    Nonfoil => "nonfoil",
+    Foil => "foil",
+    Etched => "etched",
+});
+
 macro_rules! fill_card_face_field {
     ($child:expr, $parent:expr, $field:ident) => {
         if $child.$field.is_none() {
@@ -134,7 +367,7 @@ pub struct ScryfallCard<'a> {
     pub(crate) card_faces: Option<Vec<ScryfallCard<'a>>>,
     pub(crate) content_warning: Option<bool>,
     pub(crate) cmc: Option<f32>,
-    pub(crate) image_status: Option<Cow<'a, str>>,
+    pub(crate) image_status: Option<ImageStatus>,
     pub(crate) flavor_text: Option<Cow<'a, str>>,
     pub(crate) arena_id: Option<i32>,
     pub(crate) illustration_id: Option<Cow<'a, str>>,
@@ -142,7 +375,7 @@ pub struct ScryfallCard<'a> {
     pub(crate) colors: Option<Vec<Cow<'a, str>>>,
     pub(crate) color_identity: Option<Vec<Cow<'a, str>>>,
     pub(crate) produced_mana: Option<Vec<Cow<'a, str>>>,
-    pub(crate) rarity: Option<Cow<'a, str>>,
+    pub(crate) rarity: Option<Rarity>,
     pub(crate) power: Option<Cow<'a, str>>,
     pub(crate) set_name: Option<Cow<'a, str>>,
     pub(crate) penny_rank: Option<i32>,
@@ -151,13 +384,13 @@ pub struct ScryfallCard<'a> {
     pub(crate) toughness: Option<Cow<'a, str>>,
     pub(crate) mtgo_id: Option<i32>,
     pub(crate) booster: Option<bool>,
-    pub(crate) border_color: Option<Cow<'a, str>>,
+    pub(crate) border_color: Option<BorderColor>,
     pub(crate) foil: Option<bool>,
-    pub(crate) set_type: Option<Cow<'a, str>>,
+    pub(crate) set_type: Option<SetType>,
     pub(crate) nonfoil: Option<bool>,
     pub(crate) game_changer: Option<bool>,
     pub(crate) reprint: Option<bool>,
-    pub(crate) layout: Option<Cow<'a, str>>,
+    pub(crate) layout: Option<Layout>,
     pub(crate) reserved: Option<bool>,
     pub(crate) digital: Option<bool>,
     pub(crate) set: Option<Cow<'a, str>>,
@@ -167,6 +400,15 @@ pub struct ScryfallCard<'a> {
     pub(crate) image_uris: Option<ImageUris<'a>>,
     pub(crate) games: Option<Vec<Cow<'a, str>>>,
     pub(crate) promo: Option<bool>,
+    pub(crate) collector_number: Option<Cow<'a, str>>,
+    pub(crate) released_at: Option<Cow<'a, str>>,
+    pub(crate) edhrec_rank: Option<i32>,
+    pub(crate) frame: Option<Cow<'a, str>>,
+    pub(crate) frame_effects: Option<Vec<Cow<'a, str>>>,
+    pub(crate) finishes: Option<Vec<Cow<'a, str>>>,
+    pub(crate) security_stamp: Option<Cow<'a, str>>,
+    pub(crate) watermark: Option<Cow<'a, str>>,
+    pub(crate) prices: Option<ScryfallPrices<'a>>,
 }
 
 impl<'a> ScryfallCard<'a> {
@@ -278,6 +520,15 @@ impl<'a> ScryfallCard<'a> {
                         image_uris,
                         games,
                         promo,
+                        collector_number,
+                        released_at,
+                        edhrec_rank,
+                        frame,
+                        frame_effects,
+                        finishes,
+                        security_stamp,
+                        watermark,
+                        prices,
                     ]
                 );
             }
@@ -299,66 +550,16 @@ async fn download_data<T: serde::de::DeserializeOwned>(url: &str) -> Result<T> {
     Ok(data)
 }
 
-fn card_filter(card: &ScryfallCard) -> bool {
-    // Weird vanguard cards
-    if let Some(ref st) = card.set_type {
-        if st == "vanguard" {
-            return false;
-        }
-    }
-
-    // Test play cards - there are probably others
-    if let Some(ref sn) = card.set_name {
-        if sn.contains("Mystery Booster Playtest") {
-            return false;
-        }
-    }
-
-    // Art cards
-    if let Some(ref type_line) = card.type_line {
-        if type_line.contains("Card") {
-            return false;
-        }
-    }
-
-    // Unsupported formats (i.e. 90s promotions)
-    if let Some(ref g) = card.games {
-        if g.contains(&Cow::Borrowed("sega")) || g.contains(&Cow::Borrowed("astral")) {
-            return false;
-        }
-    }
-
-    if let Some(ref type_line) = card.type_line {
-        if type_line.contains("Token") {
-            return false;
-        }
-    }
-
-    true
-}
-
-pub async fn download_latest<'a>() -> Result<Vec<ScryfallCard<'a>>> {
+pub async fn download_latest<'a>(filter: &IngestFilter) -> Result<Vec<ScryfallCard<'a>>> {
     // TODO: Temp
-    let card_file = PathBuf::from("cards.json");
-    let cards = if card_file.exists() {
-        let mut cards: Vec<ScryfallCard> =
-            serde_json::from_str(&std::fs::read_to_string(&card_file)?)?;
-        for card in cards.iter_mut() {
-            card.populate_card_faces();
-        }
-
-        cards
+    // The cache holds every card so changing the filter doesn't need a fresh download. It has
+    // its own name since the cards.json written before filters were configurable is filtered.
+    let card_file = PathBuf::from("cards-unfiltered.json");
+    let cards: Vec<ScryfallCard> = if card_file.exists() {
+        serde_json::from_str(&std::fs::read_to_string(&card_file)?)?
     } else {
         let bulk: BulkData = download_data::<BulkData>(URL).await?;
-        let data = download_data::<Vec<ScryfallCard>>(&bulk.data[0].url).await?;
-        let mut cards = data
-            .into_iter()
-            .filter(card_filter)
-            .collect::<Vec<ScryfallCard>>();
-
-        for card in cards.iter_mut() {
-            card.populate_card_faces();
-        }
+        let cards = download_data::<Vec<ScryfallCard>>(&bulk.data[0].url).await?;
 
         let out_str = serde_json::to_string_pretty(&cards)?;
         tokio::fs::write(&card_file, out_str).await?;
@@ -366,6 +567,15 @@ pub async fn download_latest<'a>() -> Result<Vec<ScryfallCard<'a>>> {
         cards
     };
 
+    let mut cards = cards
+        .into_iter()
+        .filter(|card| filter.allows(card))
+        .collect::<Vec<ScryfallCard>>();
+
+    for card in cards.iter_mut() {
+        card.populate_card_faces();
+    }
+
     Ok(cards)
 }
 
@@ -373,9 +583,27 @@ pub async fn download_latest<'a>() -> Result<Vec<ScryfallCard<'a>>> {
 mod scryfall_tests {
     use super::*;
 
+    #[test]
+    fn scryfall_enums() {
+        assert_eq!(Rarity::from("mythic"), Rarity::Mythic);
+        assert_eq!(Layout::from("modal_dfc").to_string(), "modal_dfc");
+        assert_eq!(
+            SetType::from("new_thing"),
+            SetType::Unknown("new_thing".to_string())
+        );
+        assert!(Rarity::Common < Rarity::Uncommon && Rarity::Rare < Rarity::Mythic);
+
+        let status: ImageStatus = serde_json::from_str("\"highres_scan\"").unwrap();
+        assert_eq!(status, ImageStatus::HighresScan);
+        assert_eq!(
+            serde_json::to_string(&BorderColor::Borderless).unwrap(),
+            "\"borderless\""
+        );
+    }
+
     #[tokio::test]
     async fn checker() -> anyhow::Result<()> {
-        download_latest().await?;
+        download_latest(&IngestFilter::default()).await?;
         Ok(())
     }
 }
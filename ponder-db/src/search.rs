@@ -0,0 +1,239 @@
+use crate::scryfall::{BorderColor, Format, Layout, Legality, Rarity, SetType};
+use sqlx::{QueryBuilder, Sqlite};
+
+/// Filter on power, toughness, loyalty or defense.
+///
+/// Numeric comparisons use the fixed part of variable stats, so `*` counts as 0 and `1+*` as 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatFilter {
+    Equals(i32),
+    AtLeast(i32),
+    AtMost(i32),
+    /// Only stats that aren't a plain number, e.g. `*`, `1+*` or `X`
+    Variable,
+}
+
+impl StatFilter {
+    fn push(&self, qb: &mut QueryBuilder<'_, Sqlite>, column: &str) {
+        match self {
+            Self::Equals(v) => {
+                qb.push(format!(" and {column} = ")).push_bind(*v);
+            }
+            Self::AtLeast(v) => {
+                qb.push(format!(" and {column} >= ")).push_bind(*v);
+            }
+            Self::AtMost(v) => {
+                qb.push(format!(" and {column} <= ")).push_bind(*v);
+            }
+            Self::Variable => {
+                qb.push(format!(
+                    " and {column}_text is not null and ({column} is null or {column}_text != cast({column} as text))"
+                ));
+            }
+        }
+    }
+}
+
+/// Names close to a misspelled card name, closest first
+pub fn suggest_card_names(names: &[String], name: &str, limit: usize) -> Vec<String> {
+    let needle = name.to_lowercase();
+    let max_distance = (needle.chars().count() / 3).max(2);
+    let mut scored = names
+        .iter()
+        .filter_map(|candidate| {
+            let lower = candidate.to_lowercase();
+            let distance = if lower.contains(&needle) || needle.contains(&lower) {
+                1
+            } else {
+                edit_distance(&needle, &lower)
+            };
+
+            (distance <= max_distance).then_some((distance, candidate))
+        })
+        .collect::<Vec<_>>();
+
+    scored.sort();
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, candidate)| candidate.clone())
+        .collect()
+}
+
+/// Levenshtein distance between two strings, counted in chars
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CardSearch {
+    name: Option<String>,
+    power: Option<StatFilter>,
+    toughness: Option<StatFilter>,
+    loyalty: Option<StatFilter>,
+    defense: Option<StatFilter>,
+    rarity: Option<Rarity>,
+    layout: Option<Layout>,
+    border_color: Option<BorderColor>,
+    set_type: Option<SetType>,
+    type_line: Option<String>,
+    legal_in: Option<Format>,
+    produces_within: Option<u8>,
+    exclude_reserved: bool,
+}
+
+impl CardSearch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn power(mut self, filter: StatFilter) -> Self {
+        self.power = Some(filter);
+        self
+    }
+
+    pub fn toughness(mut self, filter: StatFilter) -> Self {
+        self.toughness = Some(filter);
+        self
+    }
+
+    pub fn loyalty(mut self, filter: StatFilter) -> Self {
+        self.loyalty = Some(filter);
+        self
+    }
+
+    pub fn defense(mut self, filter: StatFilter) -> Self {
+        self.defense = Some(filter);
+        self
+    }
+
+    pub fn rarity(mut self, rarity: Rarity) -> Self {
+        self.rarity = Some(rarity);
+        self
+    }
+
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    pub fn border_color(mut self, border_color: BorderColor) -> Self {
+        self.border_color = Some(border_color);
+        self
+    }
+
+    pub fn set_type(mut self, set_type: SetType) -> Self {
+        self.set_type = Some(set_type);
+        self
+    }
+
+    /// Type line containing the text, e.g. `Land` or `Legendary Creature`
+    pub fn type_line(mut self, type_line: impl Into<String>) -> Self {
+        self.type_line = Some(type_line.into());
+        self
+    }
+
+    /// Legal or restricted in the format
+    pub fn legal_in(mut self, format: Format) -> Self {
+        self.legal_in = Some(format);
+        self
+    }
+
+    /// Makes colored mana, all of it within the colors bitmask
+    pub fn produces_within(mut self, colors: u8) -> Self {
+        self.produces_within = Some(colors);
+        self
+    }
+
+    /// Leave out cards on the reserved list
+    pub fn exclude_reserved(mut self) -> Self {
+        self.exclude_reserved = true;
+        self
+    }
+
+    pub(crate) fn build(&self) -> QueryBuilder<'_, Sqlite> {
+        let mut qb = QueryBuilder::new("select distinct * from card where 1 = 1");
+
+        if let Some(ref name) = self.name {
+            qb.push(" and name like ").push_bind(format!("%{name}%"));
+        }
+
+        for (column, filter) in [
+            ("power", &self.power),
+            ("toughness", &self.toughness),
+            ("loyalty", &self.loyalty),
+            ("defense", &self.defense),
+        ] {
+            if let Some(filter) = filter {
+                filter.push(&mut qb, column);
+            }
+        }
+
+        if let Some(ref rarity) = self.rarity {
+            qb.push(" and rarity = ").push_bind(rarity.clone());
+        }
+
+        if let Some(ref layout) = self.layout {
+            qb.push(" and layout = ").push_bind(layout.clone());
+        }
+
+        if let Some(ref border_color) = self.border_color {
+            qb.push(" and border_color = ")
+                .push_bind(border_color.clone());
+        }
+
+        if let Some(ref set_type) = self.set_type {
+            qb.push(" and set_type = ").push_bind(set_type.clone());
+        }
+
+        if let Some(ref type_line) = self.type_line {
+            qb.push(" and type_line like ")
+                .push_bind(format!("%{type_line}%"));
+        }
+
+        if let Some(ref format) = self.legal_in {
+            qb.push(" and id in (select cast(l.card_id as integer) from legality l join format f on f.id = l.format_id where f.name = ")
+                .push_bind(format.to_string())
+                .push(" and l.status in (")
+                .push_bind(Legality::Legal.to_string())
+                .push(", ")
+                .push_bind(Legality::Restricted.to_string())
+                .push("))");
+        }
+
+        if let Some(colors) = self.produces_within {
+            qb.push(" and produced_mana > 0 and produced_mana & ")
+                .push_bind(!colors as i64)
+                .push(" = 0");
+        }
+
+        if self.exclude_reserved {
+            qb.push(" and not reserved");
+        }
+
+        qb.push(" order by name, released_at desc");
+        qb
+    }
+}
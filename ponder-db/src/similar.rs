@@ -0,0 +1,467 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+use sqlx::FromRow;
+
+use crate::{
+    SqliteStore,
+    card::Card,
+    scryfall::{Format, Legality},
+};
+
+/// How much each part of the comparison counts towards the score
+const TEXT_WEIGHT: f64 = 0.6;
+const KEYWORD_WEIGHT: f64 = 0.15;
+const TYPE_WEIGHT: f64 = 0.15;
+const MANA_VALUE_WEIGHT: f64 = 0.1;
+
+const NUMBER_WORDS: [&str; 10] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+];
+
+const SELF_REFERENCES: [&str; 7] = [
+    "this creature",
+    "this artifact",
+    "this enchantment",
+    "this land",
+    "this spell",
+    "this permanent",
+    "this card",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimilarCard {
+    /// Row id of the card's first printing
+    pub id: i32,
+    pub name: String,
+    /// From 0 to 1, higher is closer
+    pub score: f64,
+}
+
+/// Lowercase rules text without reminder text, with the card's own name as `~`
+fn rules_text(name: &str, text: &str) -> String {
+    let mut text = text.to_lowercase();
+    while let Some(start) = text.find('(') {
+        let end = text[start..]
+            .find(')')
+            .map_or(text.len(), |end| start + end + 1);
+        text.replace_range(start..end, "");
+    }
+
+    let name = name.to_lowercase();
+    let mut names = vec![name.as_str()];
+    // Legends are often called by the part before the comma, e.g. `Krenko`
+    if let Some((short, _)) = name.split_once(',') {
+        names.push(short);
+    }
+    names.extend(SELF_REFERENCES);
+    for name in names {
+        text = text.replace(name, "~");
+    }
+
+    text
+}
+
+/// Rules text as words with numbers as `#`, so cards differing only in name or size still match
+pub(crate) fn normalize_oracle(name: &str, text: &str) -> String {
+    rules_text(name, text)
+        .split(|c: char| !c.is_alphanumeric() && !"~{}+-/".contains(c))
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            if NUMBER_WORDS.contains(&word) {
+                "#".to_string()
+            } else {
+                word.chars()
+                    .map(|c| if c.is_ascii_digit() { '#' } else { c })
+                    .collect()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Everything that matters when playing a card apart from its name, so functional reprints
+/// under another name, e.g. Universes Beyond cards, share a key
+pub fn gameplay_key(card: &Card) -> String {
+    format!(
+        "{}|{}|{:?}|{:?}|{:?}|{:?}|{:?}|{}",
+        card.mana_cost.as_deref().unwrap_or_default(),
+        card.type_line.as_deref().unwrap_or_default(),
+        card.colors,
+        card.power,
+        card.toughness,
+        card.loyalty,
+        card.defense,
+        rules_text(&card.name, card.oracle_text.as_deref().unwrap_or_default()).trim()
+    )
+}
+
+/// Words and pairs of neighbouring words in normalized text
+fn shingles(text: &str) -> HashSet<String> {
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    let mut shingles = words
+        .iter()
+        .map(|word| word.to_string())
+        .collect::<HashSet<_>>();
+    shingles.extend(words.windows(2).map(|pair| pair.join(" ")));
+    shingles
+}
+
+fn jaccard<T: Eq + std::hash::Hash>(a: &HashSet<T>, b: &HashSet<T>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Card types before the dash of the front face, e.g. `Legendary` and `Creature`
+fn types(type_line: Option<&str>) -> HashSet<String> {
+    let front = type_line.unwrap_or_default().split(" // ").next();
+    front
+        .and_then(|line| line.split('—').next())
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|t| t.to_string())
+        .collect()
+}
+
+#[derive(Debug, FromRow)]
+struct Candidate {
+    id: i32,
+    name: String,
+    oracle_text: Option<String>,
+    type_line: Option<String>,
+    converted_mana_cost: f32,
+}
+
+/// What's compared between two cards
+struct Profile {
+    shingles: HashSet<String>,
+    keywords: HashSet<String>,
+    types: HashSet<String>,
+    mana_value: f32,
+}
+
+impl Profile {
+    fn new(
+        name: &str,
+        oracle_text: Option<&str>,
+        type_line: Option<&str>,
+        mana_value: f32,
+        keywords: HashSet<String>,
+    ) -> Self {
+        Self {
+            shingles: shingles(&normalize_oracle(name, oracle_text.unwrap_or_default())),
+            keywords,
+            types: types(type_line),
+            mana_value,
+        }
+    }
+
+    fn score(&self, other: &Self) -> f64 {
+        let mana_value = 1.0 / (1.0 + (self.mana_value - other.mana_value).abs() as f64);
+        TEXT_WEIGHT * jaccard(&self.shingles, &other.shingles)
+            + KEYWORD_WEIGHT * jaccard(&self.keywords, &other.keywords)
+            + TYPE_WEIGHT * jaccard(&self.types, &other.types)
+            + MANA_VALUE_WEIGHT * mana_value
+    }
+}
+
+impl SqliteStore {
+    /// Cards closest to one in rules text, keywords, types and mana value, among those legal in
+    /// a format and within a color identity bitmask. Each card is only listed once.
+    pub async fn similar_cards(
+        &self,
+        card: &Card,
+        format: &Format,
+        color_identity: u8,
+        limit: usize,
+    ) -> Result<Vec<SimilarCard>> {
+        let mut similar = self
+            .similar_cards_many(&[card], format, color_identity, limit)
+            .await?;
+        Ok(similar.pop().unwrap_or_default())
+    }
+
+    /// [`Self::similar_cards`] for several cards at once, in the same order, loading the format's
+    /// cards and keywords only once
+    pub async fn similar_cards_many(
+        &self,
+        cards: &[&Card],
+        format: &Format,
+        color_identity: u8,
+        limit: usize,
+    ) -> Result<Vec<Vec<SimilarCard>>> {
+        if cards.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut keywords: HashMap<i32, HashSet<String>> = HashMap::new();
+        let rows: Vec<(i32, String)> = sqlx::query_as(
+            "select cast(ck.card_id as integer), k.name from card_keywords ck join keyword k on k.id = ck.keyword_id",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("fetching keywords")?;
+        for (id, keyword) in rows {
+            keywords.entry(id).or_default().insert(keyword);
+        }
+
+        let candidates: Vec<Candidate> = sqlx::query_as(
+            r#"
+            select c.id, c.name, c.oracle_text, c.type_line, c.converted_mana_cost
+            from card c
+            where c.id in (select min(id) from card group by coalesce(oracle_id, name))
+            and coalesce(c.color_identity, 0) & ? = 0
+            and c.id in (
+                select cast(l.card_id as integer)
+                from legality l join format f on f.id = l.format_id
+                where f.name = ? and l.status in (?, ?)
+            )
+            "#,
+        )
+        .bind(!color_identity as i64)
+        .bind(format.to_string())
+        .bind(Legality::Legal.to_string())
+        .bind(Legality::Restricted.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .with_context(|| format!("fetching {format} cards to compare"))?;
+
+        let targets = cards
+            .iter()
+            .map(|card| {
+                Profile::new(
+                    &card.name,
+                    card.oracle_text.as_deref(),
+                    card.type_line.as_deref(),
+                    card.converted_mana_cost,
+                    keywords.get(&card.id).cloned().unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let profiles = candidates
+            .iter()
+            .map(|candidate| {
+                Profile::new(
+                    &candidate.name,
+                    candidate.oracle_text.as_deref(),
+                    candidate.type_line.as_deref(),
+                    candidate.converted_mana_cost,
+                    keywords.remove(&candidate.id).unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let similar = cards
+            .iter()
+            .zip(targets.iter())
+            .map(|(card, target)| {
+                let mut similar = candidates
+                    .iter()
+                    .zip(profiles.iter())
+                    .filter(|(candidate, _)| candidate.name != card.name)
+                    .map(|(candidate, profile)| SimilarCard {
+                        id: candidate.id,
+                        name: candidate.name.clone(),
+                        score: target.score(profile),
+                    })
+                    .collect::<Vec<_>>();
+
+                similar.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.name.cmp(&b.name)));
+                similar.truncate(limit);
+                similar
+            })
+            .collect();
+
+        Ok(similar)
+    }
+
+    /// Names of other cards that play exactly the same as this one
+    pub async fn functional_duplicates(&self, card: &Card) -> Result<Vec<String>> {
+        let cards: Vec<Card> = sqlx::query_as(
+            r#"
+            select * from card
+            where mana_cost is ? and type_line is ? and name != ?
+            and id in (select min(id) from card group by coalesce(oracle_id, name))
+            order by name
+            "#,
+        )
+        .bind(&card.mana_cost)
+        .bind(&card.type_line)
+        .bind(&card.name)
+        .fetch_all(&self.pool)
+        .await
+        .with_context(|| format!("fetching cards like {}", card.name))?;
+
+        let key = gameplay_key(card);
+        let mut names = cards
+            .into_iter()
+            .filter(|other| gameplay_key(other) == key)
+            .map(|other| other.name)
+            .collect::<Vec<_>>();
+        names.dedup();
+        Ok(names)
+    }
+}
+
+#[cfg(test)]
+mod similar_tests {
+    use super::*;
+
+    #[test]
+    fn normalize() {
+        assert_eq!(
+            normalize_oracle(
+                "Krenko, Mob Boss",
+                "{T}: Create X 1/1 red Goblin creature tokens, where X is the number of Goblins you control. (Tokens are copies.) Krenko gets +2/+0."
+            ),
+            "{t} create x #/# red goblin creature tokens where x is the number of goblins you control ~ gets +#/+#"
+        );
+        assert_eq!(
+            normalize_oracle("Shock", "Shock deals two damage to any target."),
+            normalize_oracle(
+                "Lightning Bolt",
+                "Lightning Bolt deals 3 damage to any target."
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn similar_cards() -> Result<()> {
+        let store = SqliteStore::in_memory().await?;
+        let mut bolt = None;
+        for (card_id, name, text, type_line, mana_value, identity) in [
+            (
+                "bolt",
+                "Lightning Bolt",
+                "Lightning Bolt deals 3 damage to any target.",
+                "Instant",
+                1,
+                8,
+            ),
+            (
+                "shock",
+                "Shock",
+                "Shock deals 2 damage to any target.",
+                "Instant",
+                1,
+                8,
+            ),
+            (
+                "strike",
+                "Lightning Strike",
+                "Lightning Strike deals 3 damage to any target.",
+                "Instant",
+                2,
+                8,
+            ),
+            (
+                "helix",
+                "Lightning Helix",
+                "Lightning Helix deals 3 damage to any target and you gain 3 life.",
+                "Instant",
+                2,
+                9,
+            ),
+            ("bears", "Grizzly Bears", "", "Creature — Bear", 2, 16),
+        ] {
+            let id = store.insert_test_card(card_id, name).await?;
+            sqlx::query("update card set oracle_text = ?, type_line = ?, converted_mana_cost = ?, color_identity = ?, oracle_id = ? where id = ?")
+                .bind(text)
+                .bind(type_line)
+                .bind(mana_value)
+                .bind(identity)
+                .bind(card_id)
+                .bind(id)
+                .execute(&store.pool)
+                .await?;
+            store
+                .insert_test_legality(id, &Format::Modern, &Legality::Legal)
+                .await?;
+            if card_id == "bolt" {
+                bolt = store.card_by_scryfall_id(card_id).await?;
+            }
+        }
+
+        let bolt = bolt.unwrap();
+        let names = |similar: Vec<SimilarCard>| {
+            similar
+                .into_iter()
+                .map(|card| card.name)
+                .collect::<Vec<_>>()
+        };
+        let similar = store.similar_cards(&bolt, &Format::Modern, 8, 5).await?;
+        assert_eq!(names(similar), vec!["Shock", "Lightning Strike"]);
+
+        let similar = store.similar_cards(&bolt, &Format::Modern, 31, 3).await?;
+        assert_eq!(
+            names(similar),
+            vec!["Shock", "Lightning Strike", "Lightning Helix"]
+        );
+        assert!(
+            store
+                .similar_cards(&bolt, &Format::Legacy, 31, 3)
+                .await?
+                .is_empty()
+        );
+
+        let shock = store.card_by_scryfall_id("shock").await?.unwrap();
+        let similar = store
+            .similar_cards_many(&[&bolt, &shock], &Format::Modern, 8, 5)
+            .await?
+            .into_iter()
+            .map(names)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            similar,
+            vec![
+                vec!["Shock", "Lightning Strike"],
+                vec!["Lightning Bolt", "Lightning Strike"]
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn functional_duplicates() -> Result<()> {
+        let store = SqliteStore::in_memory().await?;
+        for (card_id, name, text) in [
+            (
+                "bolt",
+                "Lightning Bolt",
+                "Lightning Bolt deals 3 damage to any target.",
+            ),
+            (
+                "ub",
+                "Blaster Bolt",
+                "Blaster Bolt deals 3 damage to any target.",
+            ),
+            ("shock", "Shock", "Shock deals 2 damage to any target."),
+        ] {
+            let id = store.insert_test_card(card_id, name).await?;
+            sqlx::query("update card set oracle_text = ?, type_line = 'Instant', mana_cost = '{R}', oracle_id = ? where id = ?")
+                .bind(text)
+                .bind(card_id)
+                .bind(id)
+                .execute(&store.pool)
+                .await?;
+        }
+
+        let bolt = store.card_by_scryfall_id("bolt").await?.unwrap();
+        let ub = store.card_by_scryfall_id("ub").await?.unwrap();
+        assert_eq!(gameplay_key(&bolt), gameplay_key(&ub));
+        assert_eq!(
+            store.functional_duplicates(&bolt).await?,
+            vec!["Blaster Bolt"]
+        );
+        let shock = store.card_by_scryfall_id("shock").await?.unwrap();
+        assert!(store.functional_duplicates(&shock).await?.is_empty());
+
+        Ok(())
+    }
+}
@@ -1,6 +1,11 @@
-use crate::scryfall::{Format, ScryfallCard, download_latest};
+use crate::{
+    card::Stat,
+    filter::IngestFilter,
+    scryfall::{Format, ScryfallCard, download_latest},
+};
 use anyhow::{Context, Result};
 use sqlx::{Row, SqliteTransaction, sqlite::SqlitePool};
+use std::borrow::Cow;
 
 macro_rules! insert_image {
     ($card:expr, $txn:expr, $images:expr, $field:ident, $card_id:expr) => {
@@ -43,32 +48,38 @@ macro_rules! colors_as_u8 {
     };
 }
 
-macro_rules! string_to_integer {
+macro_rules! stat_to_integer {
     ($card:expr, $field:ident) => {
-        if let Some(ref value) = $card.$field {
-            match value.parse::<i32>() {
-                Ok(v) => Some(v),
-                Err(_) => None,
-            }
-        } else {
-            None
-        }
+        $card
+            .$field
+            .as_deref()
+            .map(Stat::from)
+            .and_then(|stat| stat.value())
     };
 }
 
 #[derive(Debug, Clone)]
 pub struct DatabaseUpdater<'a> {
     pool: &'a SqlitePool,
+    filter: IngestFilter,
 }
 
 // TODO: Deal with Card faces being ignored when added
 impl<'a> DatabaseUpdater<'a> {
     pub fn new(pool: &'a SqlitePool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            filter: IngestFilter::default(),
+        }
+    }
+
+    pub fn with_filter(mut self, filter: IngestFilter) -> Self {
+        self.filter = filter;
+        self
     }
 
     pub async fn update(&self) -> Result<()> {
-        let cards = download_latest().await?;
+        let cards = download_latest(&self.filter).await?;
 
         let mut txn = self.pool.begin().await?;
         self.add_formats(&mut txn).await?;
@@ -85,6 +96,8 @@ impl<'a> DatabaseUpdater<'a> {
                         self.add_keywords(&face, &mut txn).await?;
                         self.add_images(&face, &mut txn).await?;
                         self.add_card_types(&face, &mut txn).await?;
+                        self.add_card_lists(&face, &mut txn).await?;
+                        self.add_prices(face, &mut txn).await?;
                     }
                 } else {
                     self.add_card(&card, &mut txn).await?;
@@ -92,6 +105,8 @@ impl<'a> DatabaseUpdater<'a> {
                     self.add_keywords(&card, &mut txn).await?;
                     self.add_images(&card, &mut txn).await?;
                     self.add_card_types(&card, &mut txn).await?;
+                    self.add_card_lists(&card, &mut txn).await?;
+                    self.add_prices(card, &mut txn).await?;
                 }
             }
             txn.commit().await?;
@@ -195,7 +210,20 @@ impl<'a> DatabaseUpdater<'a> {
                 mtgo,
                 arena,
                 paper,
-                promo 
+                promo,
+                power_text,
+                toughness_text,
+                loyalty_text,
+                defense,
+                defense_text,
+                nonfoil,
+                highres_image,
+                collector_number,
+                released_at,
+                edhrec_rank,
+                frame,
+                security_stamp,
+                watermark
             ) values(
                 ?1,
                 ?2,
@@ -238,7 +266,20 @@ impl<'a> DatabaseUpdater<'a> {
                 ?39,
                 ?40,
                 ?41,
-                ?42
+                ?42,
+                ?43,
+                ?44,
+                ?45,
+                ?46,
+                ?47,
+                ?48,
+                ?49,
+                ?50,
+                ?51,
+                ?52,
+                ?53,
+                ?54,
+                ?55
             )
         "#;
 
@@ -248,7 +289,7 @@ impl<'a> DatabaseUpdater<'a> {
             .bind(&card.name)
             .bind(colors_as_u8!(card, color_indicator))
             .bind(colors_as_u8!(card, produced_mana))
-            .bind(string_to_integer!(card, loyalty))
+            .bind(stat_to_integer!(card, loyalty))
             .bind(&card.artist)
             .bind(&card.oracle_id)
             .bind(&card.type_line)
@@ -263,8 +304,8 @@ impl<'a> DatabaseUpdater<'a> {
             .bind(colors_as_u8!(card, colors))
             .bind(colors_as_u8!(card, color_identity))
             .bind(&card.rarity)
-            .bind(string_to_integer!(card, power))
-            .bind(string_to_integer!(card, toughness))
+            .bind(stat_to_integer!(card, power))
+            .bind(stat_to_integer!(card, toughness))
             .bind(&card.set_name)
             .bind(&card.set_id)
             .bind(&card.set_type)
@@ -285,6 +326,19 @@ impl<'a> DatabaseUpdater<'a> {
             .bind(&card.contains_game("arena"))
             .bind(&card.contains_game("paper"))
             .bind(&card.promo)
+            .bind(&card.power)
+            .bind(&card.toughness)
+            .bind(&card.loyalty)
+            .bind(stat_to_integer!(card, defense))
+            .bind(&card.defense)
+            .bind(card.nonfoil.unwrap_or_default())
+            .bind(card.highres_image.unwrap_or_default())
+            .bind(&card.collector_number)
+            .bind(&card.released_at)
+            .bind(&card.edhrec_rank)
+            .bind(&card.frame)
+            .bind(&card.security_stamp)
+            .bind(&card.watermark)
             .execute(txn.as_mut())
             .await
             .with_context(|| {
@@ -479,4 +533,76 @@ impl<'a> DatabaseUpdater<'a> {
 
         Ok(())
     }
+
+    // Replaced rather than ignored so prices stay current
+    async fn add_prices(
+        &self,
+        card: &ScryfallCard<'_>,
+        txn: &mut SqliteTransaction<'_>,
+    ) -> Result<()> {
+        let Some(ref prices) = card.prices else {
+            return Ok(());
+        };
+
+        let price =
+            |value: &Option<Cow<'_, str>>| value.as_deref().and_then(|v| v.parse::<f64>().ok());
+        sqlx::query("insert or replace into card_price(card_id, usd, usd_foil, usd_etched, eur, eur_foil, tix) values(?, ?, ?, ?, ?, ?, ?)")
+            .bind(&card.id)
+            .bind(price(&prices.usd))
+            .bind(price(&prices.usd_foil))
+            .bind(price(&prices.usd_etched))
+            .bind(price(&prices.eur))
+            .bind(price(&prices.eur_foil))
+            .bind(price(&prices.tix))
+            .execute(txn.as_mut())
+            .await
+            .with_context(|| format!("inserting prices - {}", card.name.as_ref().unwrap()))?;
+
+        Ok(())
+    }
+
+    async fn add_card_lists(
+        &self,
+        card: &ScryfallCard<'_>,
+        txn: &mut SqliteTransaction<'_>,
+    ) -> Result<()> {
+        let card_id: i64 = sqlx::query_scalar("select id from card where card_id = ?")
+            .bind(&card.id)
+            .fetch_one(txn.as_mut())
+            .await
+            .with_context(|| {
+                format!(
+                    "fetching card id (card lists) - {}",
+                    card.name.as_ref().unwrap()
+                )
+            })?;
+
+        for (table, column, values) in [
+            ("card_frame_effect", "frame_effect", &card.frame_effects),
+            ("card_finish", "finish", &card.finishes),
+            ("card_produced_mana", "mana", &card.produced_mana),
+        ] {
+            let Some(values) = values else {
+                continue;
+            };
+
+            for value in values.iter() {
+                sqlx::query(&format!(
+                    "insert or ignore into {table}(card_id, {column}) values(?, ?)"
+                ))
+                .bind(card_id)
+                .bind(value)
+                .execute(txn.as_mut())
+                .await
+                .with_context(|| {
+                    format!(
+                        "inserting {column} - {} {value}",
+                        card.name.as_ref().unwrap()
+                    )
+                })?;
+            }
+        }
+
+        Ok(())
+    }
 }
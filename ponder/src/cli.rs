@@ -0,0 +1,621 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use ponder_db::scryfall::{Finish, Format};
+
+use crate::{
+    Ponder,
+    data::{
+        Deck,
+        bracket::{estimate_bracket, load_combos},
+        collection::{self, Ownership},
+        commander::is_commander_format,
+        manabase, price,
+    },
+    decklist::{self, DecklistFormat, csv::CsvSite},
+};
+
+const USAGE: &str = "usage: ponder import <file or directory> [--format <format>] [--name <name>]";
+const HISTORY_USAGE: &str = "usage: ponder history <deck name>";
+const DIFF_USAGE: &str = "usage: ponder diff <from revision> <to revision>";
+const RESTORE_USAGE: &str = "usage: ponder restore <revision>";
+const BRANCH_USAGE: &str = "usage: ponder branch <revision> <new deck name>";
+const EXPORT_USAGE: &str =
+    "usage: ponder export <deck name> <file> [--csv <moxfield|archidekt|manabox> | --tagged]";
+const TAG_USAGE: &str = "usage: ponder tag <deck name> [<card name> <tag> [--remove]]";
+const LANDS_USAGE: &str = "usage: ponder lands <deck name> [--no-reserved]";
+const RECOMMEND_USAGE: &str =
+    "usage: ponder recommend <deck name> | --commander <card name> [--limit <count>]";
+const MANABASE_USAGE: &str = "usage: ponder manabase <deck name> [--games <count>] [--seed <seed>]";
+const BRACKET_USAGE: &str = "usage: ponder bracket <deck name>";
+const COST_USAGE: &str = "usage: ponder cost <deck name> [--budget <usd>]";
+const COLLECTION_USAGE: &str = "usage: ponder collection import <file> [--location <name>] | export <file> [--csv <moxfield|archidekt|manabox>] | need <deck name>";
+
+/// `ponder import <file>` - import a decklist and print any lines that couldn't be resolved.
+///
+/// Given a directory, every decklist in it is imported, each named after its file.
+pub async fn import(ponder: &Ponder, args: &[String]) -> Result<()> {
+    let mut file = None;
+    let mut format = None;
+    let mut name = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                let value = args.next().context(USAGE)?;
+                format = Some(
+                    value
+                        .parse::<Format>()
+                        .with_context(|| format!("unknown format - {value}"))?,
+                );
+            }
+            "--name" => name = Some(args.next().context(USAGE)?.clone()),
+            _ if file.is_none() => file = Some(arg.clone()),
+            _ => bail!(USAGE),
+        }
+    }
+
+    let file = PathBuf::from(file.context(USAGE)?);
+    if !file.is_dir() {
+        return import_one(ponder, &file, format, name).await;
+    }
+    if name.is_some() {
+        bail!("--name can't be used when importing a directory");
+    }
+
+    let mut files = std::fs::read_dir(&file)
+        .with_context(|| format!("reading directory - {}", file.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(|| format!("reading directory - {}", file.display()))?;
+    files.retain(|path| path.is_file());
+    files.sort();
+
+    for path in files {
+        // Keep going so one bad file doesn't stop the rest
+        if let Err(e) = import_one(ponder, &path, format.clone(), None).await {
+            eprintln!("{}: {e:#}", path.display());
+        }
+    }
+
+    Ok(())
+}
+
+async fn import_one(
+    ponder: &Ponder,
+    file: &Path,
+    format: Option<Format>,
+    name: Option<String>,
+) -> Result<()> {
+    let mut report = decklist::import_file(&ponder.store, file, format).await?;
+    if let Some(name) = name {
+        report.deck.name = name;
+    }
+
+    for unresolved in report.unresolved.iter() {
+        let line = &unresolved.line;
+        eprintln!("line {}: couldn't find {}", line.line_number, line.name);
+        if !unresolved.suggestions.is_empty() {
+            eprintln!("    did you mean: {}", unresolved.suggestions.join(", "));
+        }
+    }
+
+    let cards = report
+        .deck
+        .cards
+        .iter()
+        .map(|entry| entry.quantity as usize)
+        .sum::<usize>();
+    report.deck.save(&ponder.store, Some("Imported")).await?;
+    println!(
+        "imported {} ({}) with {cards} cards, {} lines unresolved",
+        report.deck.name,
+        report.deck.format,
+        report.unresolved.len()
+    );
+
+    Ok(())
+}
+
+async fn find_deck(ponder: &Ponder, name: &str) -> Result<i64> {
+    let summary = ponder
+        .store
+        .list_decks()
+        .await?
+        .into_iter()
+        .find(|deck| deck.name.eq_ignore_ascii_case(name))
+        .with_context(|| format!("no deck named {name}"))?;
+
+    Ok(summary.id)
+}
+
+/// `ponder export <deck name> <file>` - write a saved deck in the format matching the file extension
+pub async fn export(ponder: &Ponder, args: &[String]) -> Result<()> {
+    let (name, file, format) = match args {
+        [name, file] => (name, file, DecklistFormat::from_path(file)),
+        [name, file, flag, site] if flag == "--csv" => {
+            (name, file, DecklistFormat::Csv(site.parse()?))
+        }
+        [name, file, flag] if flag == "--tagged" => (name, file, DecklistFormat::TaggedText),
+        _ => bail!(EXPORT_USAGE),
+    };
+
+    let deck = Deck::from(
+        ponder
+            .store
+            .load_deck(find_deck(ponder, name).await?)
+            .await?,
+    );
+    decklist::export_file_as(&deck, file, format)?;
+    println!("exported {} to {file}", deck.name);
+
+    Ok(())
+}
+
+/// `ponder history <deck name>` - list the saved revisions of a deck
+pub async fn history(ponder: &Ponder, args: &[String]) -> Result<()> {
+    let [name] = args else {
+        bail!(HISTORY_USAGE);
+    };
+
+    for revision in ponder
+        .store
+        .list_revisions(find_deck(ponder, name).await?)
+        .await?
+    {
+        println!(
+            "{:>5}  {}  {}",
+            revision.id,
+            revision.created_at,
+            revision.note.unwrap_or_default()
+        );
+    }
+
+    Ok(())
+}
+
+/// `ponder diff <from> <to>` - cards added, removed and changed between two revisions
+pub async fn diff(ponder: &Ponder, args: &[String]) -> Result<()> {
+    let [from, to] = args else {
+        bail!(DIFF_USAGE);
+    };
+
+    let diff = ponder
+        .store
+        .diff_revisions(
+            from.parse().context(DIFF_USAGE)?,
+            to.parse().context(DIFF_USAGE)?,
+        )
+        .await?;
+
+    for change in diff.changes.iter() {
+        let delta = change.after as i64 - change.before as i64;
+        let mut line = format!("{delta:+} {} ({})", change.name, change.zone.label());
+        if change.finish != Finish::Nonfoil {
+            line.push_str(&format!(" {}", change.finish));
+        }
+        for tag in change.tags_added.iter() {
+            line.push_str(&format!(" +#{tag}"));
+        }
+        for tag in change.tags_removed.iter() {
+            line.push_str(&format!(" -#{tag}"));
+        }
+        println!("{line}");
+    }
+
+    Ok(())
+}
+
+/// `ponder restore <revision>` - put a deck back the way it was at a revision
+pub async fn restore(ponder: &Ponder, args: &[String]) -> Result<()> {
+    let [revision] = args else {
+        bail!(RESTORE_USAGE);
+    };
+
+    let id = ponder
+        .store
+        .restore_revision(revision.parse().context(RESTORE_USAGE)?, None)
+        .await?;
+    println!("restored as revision {id}");
+
+    Ok(())
+}
+
+/// `ponder branch <revision> <name>` - start a new deck from an old revision
+pub async fn branch(ponder: &Ponder, args: &[String]) -> Result<()> {
+    let [revision, name] = args else {
+        bail!(BRANCH_USAGE);
+    };
+
+    ponder
+        .store
+        .branch_revision(revision.parse().context(BRANCH_USAGE)?, name)
+        .await?;
+    println!("created {name} from revision {revision}");
+
+    Ok(())
+}
+
+/// `ponder tag <deck name> <card name> <tag>` - tag every entry of a card in a deck.
+///
+/// With only the deck name, prints how many cards have each tag.
+pub async fn tag(ponder: &Ponder, args: &[String]) -> Result<()> {
+    let (name, card, tag, remove) = match args {
+        [name] => {
+            let deck_id = find_deck(ponder, name).await?;
+            for (tag, count) in ponder.store.deck_tags(deck_id).await? {
+                println!("{count:>4} {tag}");
+            }
+            return Ok(());
+        }
+        [name, card, tag] => (name, card, tag, false),
+        [name, card, tag, flag] if flag == "--remove" => (name, card, tag, true),
+        _ => bail!(TAG_USAGE),
+    };
+
+    let deck = Deck::from(
+        ponder
+            .store
+            .load_deck(find_deck(ponder, name).await?)
+            .await?,
+    );
+    let deck_id = deck.id.context("deck has no id")?;
+    let entries = deck
+        .cards
+        .iter()
+        .filter(|entry| entry.card.name.eq_ignore_ascii_case(card))
+        .collect::<Vec<_>>();
+    if entries.is_empty() {
+        bail!("{} has no card named {card}", deck.name);
+    }
+
+    for entry in entries {
+        if remove {
+            ponder
+                .store
+                .remove_tag(deck_id, &entry.card.card_id, entry.zone, tag)
+                .await?;
+        } else {
+            ponder
+                .store
+                .add_tag(deck_id, &entry.card.card_id, entry.zone, tag)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `ponder manabase <deck name>` - simulate games to see how often each spell is castable on
+/// curve, and compare the colored sources against Karsten's targets
+pub async fn manabase(ponder: &Ponder, args: &[String]) -> Result<()> {
+    let mut args = args.iter();
+    let name = args.next().context(MANABASE_USAGE)?;
+    let mut games = manabase::DEFAULT_GAMES;
+    let mut seed = 0;
+    while let Some(arg) = args.next() {
+        let value = args.next().context(MANABASE_USAGE)?;
+        match arg.as_str() {
+            "--games" => games = value.parse().context("parsing game count")?,
+            "--seed" => seed = value.parse().context("parsing seed")?,
+            _ => bail!(MANABASE_USAGE),
+        }
+    }
+
+    let deck = Deck::from(
+        ponder
+            .store
+            .load_deck(find_deck(ponder, name).await?)
+            .await?,
+    );
+    let report = manabase::analyze(&deck, games, seed);
+
+    println!("{} games, seed {}", report.games, report.seed);
+    for color in &report.colors {
+        let short = if color.is_short() { " - short" } else { "" };
+        println!(
+            "{:?}: {} sources, {} wanted for {}{short}",
+            color.color, color.sources, color.needed, color.card
+        );
+    }
+
+    println!();
+    for spell in &report.spells {
+        println!(
+            "{:>5.1}% {} (turn {})",
+            spell.on_curve * 100.0,
+            spell.name,
+            spell.mana_value.max(1)
+        );
+    }
+
+    Ok(())
+}
+
+/// `ponder lands <deck name>` - suggest a land count and land base for a deck's spells
+pub async fn lands(ponder: &Ponder, args: &[String]) -> Result<()> {
+    let (name, exclude_reserved) = match args {
+        [name] => (name, false),
+        [name, flag] if flag == "--no-reserved" => (name, true),
+        _ => bail!(LANDS_USAGE),
+    };
+
+    let deck = Deck::from(
+        ponder
+            .store
+            .load_deck(find_deck(ponder, name).await?)
+            .await?,
+    );
+    let suggestion = deck.suggest_lands(&ponder.store, exclude_reserved).await?;
+
+    println!(
+        "{} lands for an average mana value of {:.2} with {} ramp cards",
+        suggestion.lands, suggestion.average_mana_value, suggestion.ramp
+    );
+    for (card, quantity) in &suggestion.nonbasics {
+        println!("{quantity} {}", card.name);
+    }
+    for (color, quantity) in &suggestion.basics {
+        println!("{quantity} {}", manabase::basic_land_name(*color));
+    }
+
+    Ok(())
+}
+
+/// `ponder recommend <deck name>` - suggest cards played alongside a deck's cards, or a
+/// commander's, across every stored deck
+pub async fn recommend(ponder: &Ponder, args: &[String]) -> Result<()> {
+    let mut deck_name = None;
+    let mut commander = None;
+    let mut limit = 20;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--commander" => commander = Some(args.next().context(RECOMMEND_USAGE)?.clone()),
+            "--limit" => {
+                limit = args
+                    .next()
+                    .context(RECOMMEND_USAGE)?
+                    .parse()
+                    .context("parsing limit")?
+            }
+            _ if deck_name.is_none() => deck_name = Some(arg.clone()),
+            _ => bail!(RECOMMEND_USAGE),
+        }
+    }
+
+    let (seeds, exclude) = match (deck_name, commander) {
+        (Some(name), None) => {
+            let deck = Deck::from(
+                ponder
+                    .store
+                    .load_deck(find_deck(ponder, &name).await?)
+                    .await?,
+            );
+            let seeds = deck
+                .cards
+                .iter()
+                .filter(|entry| entry.zone.is_deck())
+                .map(|entry| entry.card.name.clone())
+                .collect::<Vec<_>>();
+            (seeds, deck.id)
+        }
+        (None, Some(commander)) => {
+            let card = ponder
+                .store
+                .card_by_name(&commander)
+                .await?
+                .with_context(|| format!("no card named {commander}"))?;
+            (vec![card.name], None)
+        }
+        _ => bail!(RECOMMEND_USAGE),
+    };
+
+    let index = ponder.store.cooccurrence_index().await?;
+    let seeds = seeds.iter().map(String::as_str).collect::<Vec<_>>();
+    let recommendations = index.recommend(&seeds, exclude, limit);
+    if recommendations.is_empty() {
+        println!("nothing to recommend from {} decks", index.deck_count());
+    }
+
+    for recommendation in recommendations {
+        println!(
+            "{:>+5.0}% synergy, in {:>3.0}% - {}",
+            recommendation.synergy * 100.0,
+            recommendation.inclusion * 100.0,
+            recommendation.name
+        );
+    }
+
+    Ok(())
+}
+
+/// `ponder cost <deck name>` - price a deck as printed and with its cheapest legal printings.
+///
+/// With `--budget`, suggest cheaper alternatives for every card costing more than the budget.
+pub async fn cost(ponder: &Ponder, args: &[String]) -> Result<()> {
+    let (name, budget) = match args {
+        [name] => (name, None),
+        [name, flag, budget] if flag == "--budget" => {
+            (name, Some(budget.parse::<f64>().context("parsing budget")?))
+        }
+        _ => bail!(COST_USAGE),
+    };
+
+    let deck = Deck::from(
+        ponder
+            .store
+            .load_deck(find_deck(ponder, name).await?)
+            .await?,
+    );
+    let report = deck.cost(&ponder.store).await?;
+
+    println!(
+        "${:.2} as printed, ${:.2} with the cheapest printings",
+        report.total, report.cheapest
+    );
+    for zone in &report.zones {
+        println!(
+            "{}: ${:.2} / ${:.2}",
+            zone.zone.label(),
+            zone.total,
+            zone.cheapest
+        );
+    }
+
+    println!();
+    for card in report.most_expensive(price::MOST_EXPENSIVE) {
+        let cheapest = card
+            .cheapest
+            .as_ref()
+            .map(|(set, price)| format!(" (${price:.2} in {set})"))
+            .unwrap_or_default();
+        println!(
+            "${:>8.2} {} {}{cheapest}",
+            card.total().unwrap_or_default(),
+            card.quantity,
+            card.name
+        );
+    }
+
+    let unpriced = report
+        .unpriced()
+        .map(|card| card.name.as_str())
+        .collect::<Vec<_>>();
+    if !unpriced.is_empty() {
+        println!("\nno price for {}", unpriced.join(", "));
+    }
+
+    if let Some(budget) = budget {
+        println!();
+        for swap in deck.budget(&ponder.store, budget).await? {
+            let alternatives = swap
+                .alternatives
+                .iter()
+                .map(|(name, price)| format!("{name} (${price:.2})"))
+                .collect::<Vec<_>>();
+            if alternatives.is_empty() {
+                println!("{} (${:.2}): nothing cheaper", swap.card, swap.price);
+            } else {
+                println!(
+                    "{} (${:.2}): {}",
+                    swap.card,
+                    swap.price,
+                    alternatives.join(", ")
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// `ponder collection <import|export|need>` - import or export owned cards as CSV, or list the
+/// cards a deck still needs
+pub async fn collection(ponder: &Ponder, args: &[String]) -> Result<()> {
+    match args {
+        [command, file, rest @ ..] if command == "import" => {
+            let location = match rest {
+                [] => None,
+                [flag, location] if flag == "--location" => Some(location.as_str()),
+                _ => bail!(COLLECTION_USAGE),
+            };
+
+            let import = collection::import_collection(&ponder.store, file, location).await?;
+            for unresolved in import.unresolved.iter() {
+                let line = &unresolved.line;
+                eprintln!("line {}: couldn't find {}", line.line_number, line.name);
+                if !unresolved.suggestions.is_empty() {
+                    eprintln!("    did you mean: {}", unresolved.suggestions.join(", "));
+                }
+            }
+            println!(
+                "added {} cards, {} rows unresolved",
+                import.added,
+                import.unresolved.len()
+            );
+        }
+        [command, file, rest @ ..] if command == "export" => {
+            let site = match rest {
+                [] => CsvSite::ManaBox,
+                [flag, site] if flag == "--csv" => site.parse()?,
+                _ => bail!(COLLECTION_USAGE),
+            };
+
+            let cards = collection::export_collection(&ponder.store, file, site).await?;
+            println!("exported {cards} cards to {file}");
+        }
+        [command, name] if command == "need" => {
+            let deck = Deck::from(
+                ponder
+                    .store
+                    .load_deck(find_deck(ponder, name).await?)
+                    .await?,
+            );
+            let status = deck.collection_status(&ponder.store).await?;
+            for (name, copies) in status.needed() {
+                println!("{copies} {name}");
+            }
+
+            let mut entries = deck.cards.iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.card.name.cmp(&b.card.name));
+            entries.dedup_by(|a, b| a.card.name == b.card.name);
+            for entry in entries {
+                if let Some(Ownership::InOtherDecks(decks)) = status.get(&entry.card.name) {
+                    println!("{} is in {}", entry.card.name, decks.join(", "));
+                }
+            }
+        }
+        _ => bail!(COLLECTION_USAGE),
+    }
+
+    Ok(())
+}
+
+/// `ponder bracket <deck name>` - estimate a commander deck's bracket and explain why
+pub async fn bracket(ponder: &Ponder, args: &[String]) -> Result<()> {
+    let [name] = args else {
+        bail!(BRACKET_USAGE);
+    };
+
+    let deck = Deck::from(
+        ponder
+            .store
+            .load_deck(find_deck(ponder, name).await?)
+            .await?,
+    );
+    if !is_commander_format(&deck.format) {
+        bail!(
+            "brackets are only for commander formats, {} is {}",
+            deck.name,
+            deck.format
+        );
+    }
+
+    let combos = load_combos(&ponder.workspace)?;
+    let report = estimate_bracket(&deck, &combos);
+
+    println!("bracket {}", report.bracket);
+    for reason in &report.reasons {
+        println!("- {reason}");
+    }
+
+    for (label, cards) in [
+        ("game changers", &report.game_changers),
+        ("mass land destruction", &report.mass_land_destruction),
+        ("extra turns", &report.extra_turns),
+        ("tutors", &report.tutors),
+    ] {
+        if !cards.is_empty() {
+            println!("{label}: {}", cards.join(", "));
+        }
+    }
+
+    for combo in &report.combos {
+        println!("combo: {} - {}", combo.cards.join(" + "), combo.description);
+    }
+
+    Ok(())
+}
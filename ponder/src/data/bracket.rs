@@ -0,0 +1,256 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use ponder_db::deck::Zone;
+use serde::Deserialize;
+
+use super::{CardExt, Deck};
+
+const DEFAULT_COMBOS: &str = include_str!("combos.json");
+
+/// A known combo that wins or goes infinite with just these cards
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Combo {
+    pub cards: Vec<String>,
+    pub description: String,
+}
+
+/// Load the combo list from `combos.json` in the workspace, falling back to the bundled list
+pub fn load_combos(workspace: impl AsRef<Path>) -> Result<Vec<Combo>> {
+    let path = workspace.as_ref().join("combos.json");
+    let combos = if path.exists() {
+        let data = std::fs::read_to_string(&path)
+            .with_context(|| format!("reading combo list - {}", path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("parsing combo list - {}", path.display()))?
+    } else {
+        serde_json::from_str(DEFAULT_COMBOS).context("parsing bundled combo list")?
+    };
+
+    Ok(combos)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BracketReport {
+    /// Estimated commander bracket, 1 (exhibition) to 5 (cEDH)
+    pub bracket: u8,
+    pub game_changers: Vec<String>,
+    pub mass_land_destruction: Vec<String>,
+    pub extra_turns: Vec<String>,
+    pub tutors: Vec<String>,
+    pub combos: Vec<Combo>,
+    pub reasons: Vec<String>,
+}
+
+fn is_mass_land_destruction(text: &str) -> bool {
+    text.split(['.', '\n']).any(|sentence| {
+        let hits_lands = sentence.contains("all lands")
+            || sentence.contains("all nonbasic lands")
+            || sentence.contains("creatures, and lands");
+        let removes = ["destroy", "exile", "return", "sacrifice"]
+            .iter()
+            .any(|verb| sentence.contains(verb));
+
+        (hits_lands && removes) || sentence.contains("lands don't untap")
+    })
+}
+
+fn is_extra_turn(text: &str) -> bool {
+    text.contains("extra turn after this one")
+        || text.contains("take an extra turn")
+        || text.contains("takes an extra turn")
+}
+
+/// Searching for lands doesn't count as a tutor
+fn is_tutor(text: &str) -> bool {
+    text.split(['.', '\n']).any(|sentence| {
+        sentence.contains("search your library for")
+            && !sentence.contains("land card")
+            && !sentence.contains("basic land")
+            && !["plains", "island", "swamp", "mountain", "forest"]
+                .iter()
+                .any(|land| sentence.contains(land))
+    })
+}
+
+/// Estimate a deck's commander bracket from game changers, land destruction, extra turns,
+/// tutors and known two-card combos
+pub fn estimate_bracket(deck: &Deck, combos: &[Combo]) -> BracketReport {
+    let mut report = BracketReport::default();
+
+    let cards = deck
+        .cards
+        .iter()
+        .filter(|entry| entry.zone.is_deck() || entry.zone == Zone::Companion)
+        .map(|entry| &entry.card)
+        .collect::<Vec<_>>();
+
+    for card in cards.iter() {
+        let text = card.rules_lines().join("\n").to_lowercase();
+        let name = card.name.clone();
+
+        if card.game_changer {
+            report.game_changers.push(name.clone());
+        }
+
+        if is_mass_land_destruction(&text) {
+            report.mass_land_destruction.push(name.clone());
+        }
+
+        if is_extra_turn(&text) {
+            report.extra_turns.push(name.clone());
+        }
+
+        if is_tutor(&text) {
+            report.tutors.push(name);
+        }
+    }
+
+    report.combos = combos
+        .iter()
+        .filter(|combo| {
+            combo
+                .cards
+                .iter()
+                .all(|name| cards.iter().any(|card| card.name == *name))
+        })
+        .cloned()
+        .collect();
+
+    let game_changers = report.game_changers.len();
+    let tutors = report.tutors.len();
+    let extra_turns = report.extra_turns.len();
+    let combos = report.combos.len();
+    let mut reasons = Vec::new();
+
+    let bracket = if game_changers > 3 && combos > 0 && tutors > 4 {
+        reasons.push(format!(
+            "{game_changers} game changers, {combos} combos and {tutors} tutors look competitive"
+        ));
+        5
+    } else if !report.mass_land_destruction.is_empty() || game_changers > 3 {
+        if !report.mass_land_destruction.is_empty() {
+            reasons.push("mass land destruction is only allowed from bracket 4".to_string());
+        }
+        if game_changers > 3 {
+            reasons.push(format!(
+                "{game_changers} game changers, bracket 3 allows up to 3"
+            ));
+        }
+        4
+    } else if game_changers > 0 || combos > 0 || extra_turns > 1 || tutors > 3 {
+        if game_changers > 0 {
+            reasons.push(format!("{game_changers} game changers need bracket 3"));
+        }
+        if combos > 0 {
+            reasons.push(format!(
+                "{combos} two-card combos, bracket 3 only allows them late in the game"
+            ));
+        }
+        if extra_turns > 1 {
+            reasons.push(format!("{extra_turns} extra turn cards could be chained"));
+        }
+        if tutors > 3 {
+            reasons.push(format!("{tutors} tutors"));
+        }
+        3
+    } else if tutors > 0 || extra_turns > 0 {
+        reasons.push(format!(
+            "{tutors} tutors and {extra_turns} extra turn cards, kept to a few"
+        ));
+        2
+    } else {
+        reasons.push("no game changers, tutors, extra turns or combos".to_string());
+        1
+    };
+
+    report.bracket = bracket;
+    report.reasons = reasons;
+    report
+}
+
+#[cfg(test)]
+mod bracket_tests {
+    use super::*;
+    use crate::data::{TestCard, test_card, test_deck};
+    use ponder_db::scryfall::Format;
+
+    #[test]
+    fn detects_cards() {
+        assert!(is_mass_land_destruction(
+            &"Destroy all lands.".to_lowercase()
+        ));
+        assert!(is_mass_land_destruction(
+            &"Destroy all artifacts, creatures, and lands. They can't be regenerated."
+                .to_lowercase()
+        ));
+        assert!(!is_mass_land_destruction(
+            &"Destroy target land.".to_lowercase()
+        ));
+        assert!(is_tutor(
+            &"Search your library for a card, put that card into your hand, then shuffle. You lose 2 life."
+                .to_lowercase()
+        ));
+        assert!(!is_tutor(
+            &"Search your library for a basic land card, put it onto the battlefield tapped, then shuffle."
+                .to_lowercase()
+        ));
+        assert!(is_extra_turn(
+            &"Take an extra turn after this one.".to_lowercase()
+        ));
+    }
+
+    #[test]
+    fn brackets() -> Result<()> {
+        let combos = load_combos("/nonexistent")?;
+        assert!(!combos.is_empty());
+
+        let casual = test_deck(
+            Format::Commander,
+            [
+                test_card(1, "Grizzly Bears"),
+                test_card(2, "Llanowar Elves").with_text("{T}: Add {G}."),
+            ]
+            .map(|card| (card, 1, Zone::Main)),
+        );
+        assert_eq!(estimate_bracket(&casual, &combos).bracket, 1);
+
+        let tutor = test_deck(
+            Format::Commander,
+            [test_card(1, "Demonic Tutor").with_text(
+                "Search your library for a card, put that card into your hand, then shuffle.",
+            )]
+            .map(|card| (card, 1, Zone::Main)),
+        );
+        assert_eq!(estimate_bracket(&tutor, &combos).bracket, 2);
+
+        let combo = test_deck(
+            Format::Commander,
+            [
+                test_card(1, "Thassa's Oracle"),
+                test_card(2, "Demonic Consultation"),
+            ]
+            .map(|card| (card, 1, Zone::Main)),
+        );
+        let report = estimate_bracket(&combo, &combos);
+        assert_eq!(report.bracket, 3);
+        assert_eq!(report.combos.len(), 1);
+
+        let mut lotus = test_card(1, "Mana Vault");
+        lotus.game_changer = true;
+        let mld = test_deck(
+            Format::Commander,
+            [
+                lotus,
+                test_card(2, "Armageddon").with_text("Destroy all lands."),
+            ]
+            .map(|card| (card, 1, Zone::Main)),
+        );
+        let report = estimate_bracket(&mld, &combos);
+        assert_eq!(report.bracket, 4);
+        assert_eq!(report.game_changers, vec!["Mana Vault"]);
+        assert_eq!(report.mass_land_destruction, vec!["Armageddon"]);
+
+        Ok(())
+    }
+}
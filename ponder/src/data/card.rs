@@ -0,0 +1,113 @@
+use ponder_db::card::Card;
+
+const PERMANENT_TYPES: [&str; 6] = [
+    "Artifact",
+    "Battle",
+    "Creature",
+    "Enchantment",
+    "Land",
+    "Planeswalker",
+];
+
+/// Type line and rules text helpers for deck building
+pub trait CardExt {
+    /// Type line of the front face, e.g. `Legendary Creature — Elf Druid`
+    fn front_type_line(&self) -> &str;
+    /// Supertypes and card types, e.g. `["Legendary", "Creature"]`
+    fn types(&self) -> Vec<&str>;
+    fn subtypes(&self) -> Vec<&str>;
+    /// Oracle text lines with reminder text removed
+    fn rules_lines(&self) -> Vec<String>;
+
+    fn is_type(&self, card_type: &str) -> bool {
+        self.types().contains(&card_type)
+    }
+
+    fn has_subtype(&self, subtype: &str) -> bool {
+        self.subtypes().contains(&subtype)
+    }
+
+    fn is_land(&self) -> bool {
+        self.is_type("Land")
+    }
+
+    fn is_basic_land(&self) -> bool {
+        self.is_type("Basic") && self.is_land()
+    }
+
+    fn is_permanent(&self) -> bool {
+        PERMANENT_TYPES.iter().any(|t| self.is_type(t))
+    }
+}
+
+impl CardExt for Card {
+    fn front_type_line(&self) -> &str {
+        let type_line = self.type_line.as_deref().unwrap_or_default();
+        type_line.split(" // ").next().unwrap_or_default()
+    }
+
+    fn types(&self) -> Vec<&str> {
+        let (types, _) = self
+            .front_type_line()
+            .split_once("—")
+            .unwrap_or((self.front_type_line(), ""));
+        types.split_whitespace().collect()
+    }
+
+    fn subtypes(&self) -> Vec<&str> {
+        match self.front_type_line().split_once("—") {
+            Some((_, subtypes)) => subtypes.split_whitespace().collect(),
+            None => vec![],
+        }
+    }
+
+    fn rules_lines(&self) -> Vec<String> {
+        let text = self.oracle_text.as_deref().unwrap_or_default();
+        let text = text.split("\n//\n").next().unwrap_or_default();
+
+        text.lines()
+            .map(|line| {
+                let mut out = String::new();
+                let mut depth = 0;
+                for c in line.chars() {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ if depth == 0 => out.push(c),
+                        _ => {}
+                    }
+                }
+                out.trim().to_string()
+            })
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod card_tests {
+    use super::*;
+    use crate::data::test_card;
+
+    #[test]
+    fn card_types() {
+        let mut card = test_card(1, "Dryad Arbor");
+        card.type_line = Some("Land Creature — Forest Dryad".to_string());
+        assert_eq!(card.types(), vec!["Land", "Creature"]);
+        assert_eq!(card.subtypes(), vec!["Forest", "Dryad"]);
+        assert!(card.is_land() && card.is_permanent() && !card.is_basic_land());
+
+        card.type_line = Some("Instant // Sorcery".to_string());
+        assert!(card.is_type("Instant") && !card.is_permanent());
+    }
+
+    #[test]
+    fn rules_lines() {
+        let mut card = test_card(1, "Kraum");
+        card.oracle_text = Some(
+            "Partner (You can have two commanders if both have partner.)\nFlying, haste"
+                .to_string(),
+        );
+        assert_eq!(card.rules_lines(), vec!["Partner", "Flying, haste"]);
+    }
+}
@@ -0,0 +1,219 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{Context, Result};
+use ponder_db::{SqliteStore, collection::CollectionRecord};
+
+use super::{Deck, card::CardExt};
+use crate::decklist::{
+    Unresolved,
+    csv::{self, CsvSite},
+};
+
+/// Whether the collection covers a card a deck plays
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ownership {
+    Owned,
+    /// Owned, but not enough copies are free without taking them from these decks
+    InOtherDecks(Vec<String>),
+    /// Copies still to acquire
+    Needed(u32),
+}
+
+/// Ownership of each card a deck plays, keyed by name. Basic lands are assumed owned.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CollectionStatus {
+    cards: HashMap<String, Ownership>,
+}
+
+impl CollectionStatus {
+    /// Compare a deck against owned copies and the copies other decks play, both keyed by name.
+    ///
+    /// Nothing is marked until the collection has at least one card.
+    pub fn new(
+        deck: &Deck,
+        owned: &HashMap<String, u32>,
+        in_decks: &HashMap<String, Vec<(String, u32)>>,
+    ) -> Self {
+        let mut status = Self::default();
+        if owned.is_empty() {
+            return status;
+        }
+
+        let mut wanted: HashMap<&str, u32> = HashMap::new();
+        for entry in &deck.cards {
+            if entry.zone.is_played() && !entry.card.is_basic_land() {
+                *wanted.entry(&entry.card.name).or_default() += entry.quantity;
+            }
+        }
+
+        for (name, wanted) in wanted {
+            let owned = owned.get(name).copied().unwrap_or_default();
+            let decks = in_decks.get(name).map(Vec::as_slice).unwrap_or_default();
+            let elsewhere = decks.iter().map(|(_, quantity)| quantity).sum::<u32>();
+
+            let ownership = if owned < wanted {
+                Ownership::Needed(wanted - owned)
+            } else if owned.saturating_sub(elsewhere) < wanted {
+                Ownership::InOtherDecks(decks.iter().map(|(deck, _)| deck.clone()).collect())
+            } else {
+                Ownership::Owned
+            };
+            status.cards.insert(name.to_string(), ownership);
+        }
+
+        status
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Ownership> {
+        self.cards.get(name)
+    }
+
+    /// Cards still to acquire and how many copies, by name
+    pub fn needed(&self) -> Vec<(&str, u32)> {
+        let mut needed = self
+            .cards
+            .iter()
+            .filter_map(|(name, ownership)| match ownership {
+                Ownership::Needed(copies) => Some((name.as_str(), *copies)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        needed.sort();
+        needed
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CollectionImport {
+    pub added: u32,
+    pub unresolved: Vec<Unresolved>,
+}
+
+impl Deck {
+    pub async fn collection_status(&self, store: &SqliteStore) -> Result<CollectionStatus> {
+        let owned = store.owned_cards().await?;
+        let in_decks = store.cards_in_decks(self.id).await?;
+        Ok(CollectionStatus::new(self, &owned, &in_decks))
+    }
+}
+
+/// Add the cards in a collection CSV to the collection, optionally putting them all in one
+/// location over any the CSV gives
+pub async fn import_collection(
+    store: &SqliteStore,
+    path: impl AsRef<Path>,
+    location: Option<&str>,
+) -> Result<CollectionImport> {
+    let path = path.as_ref();
+    let data = std::fs::read_to_string(path)
+        .with_context(|| format!("reading collection - {}", path.display()))?;
+    let rows = csv::parse_collection(&data, CsvSite::ManaBox)
+        .with_context(|| format!("parsing collection - {}", path.display()))?;
+
+    let mut import = CollectionImport::default();
+    let mut records = Vec::new();
+    let mut unresolved = Vec::new();
+    for row in rows {
+        let Some(card) = row.line.resolve(store).await? else {
+            unresolved.push(row.line);
+            continue;
+        };
+
+        import.added += row.line.quantity;
+        records.push(CollectionRecord {
+            card_id: card.card_id,
+            finish: row.line.finish,
+            condition: row.condition,
+            language: row.language,
+            location: location.map(str::to_string).or(row.location),
+            quantity: row.line.quantity,
+        });
+    }
+
+    store.add_to_collection(&records).await?;
+    import.unresolved = Unresolved::suggest(store, unresolved).await?;
+    Ok(import)
+}
+
+/// Write the whole collection to a CSV laid out for a site, returning the cards written
+pub async fn export_collection(
+    store: &SqliteStore,
+    path: impl AsRef<Path>,
+    site: CsvSite,
+) -> Result<u32> {
+    let path = path.as_ref();
+    let collection = store.load_collection().await?;
+    let data = site.mapping().export_collection(&collection)?;
+    std::fs::write(path, data)
+        .with_context(|| format!("writing collection - {}", path.display()))?;
+
+    Ok(collection.iter().map(|entry| entry.quantity).sum())
+}
+
+#[cfg(test)]
+mod collection_tests {
+    use super::*;
+    use crate::data::test_card;
+    use ponder_db::{
+        deck::Zone,
+        scryfall::{Finish, Format},
+    };
+
+    #[test]
+    fn collection_status() {
+        let mut deck = Deck::new("Burn", Format::Modern);
+        deck.add(
+            test_card(1, "Lightning Bolt"),
+            4,
+            Zone::Main,
+            Finish::Nonfoil,
+        );
+        deck.add(test_card(2, "Goblin Guide"), 4, Zone::Main, Finish::Nonfoil);
+        deck.add(
+            test_card(3, "Eidolon of the Great Revel"),
+            2,
+            Zone::Sideboard,
+            Finish::Nonfoil,
+        );
+        deck.add(
+            test_card(4, "Skullcrack"),
+            1,
+            Zone::Maybeboard,
+            Finish::Nonfoil,
+        );
+        let mut mountain = test_card(5, "Mountain");
+        mountain.type_line = Some("Basic Land — Mountain".to_string());
+        deck.add(mountain, 12, Zone::Main, Finish::Nonfoil);
+
+        assert_eq!(
+            CollectionStatus::new(&deck, &HashMap::new(), &HashMap::new()),
+            CollectionStatus::default()
+        );
+
+        let owned = HashMap::from([
+            ("Lightning Bolt".to_string(), 6),
+            ("Goblin Guide".to_string(), 5),
+            ("Eidolon of the Great Revel".to_string(), 1),
+        ]);
+        let in_decks = HashMap::from([
+            ("Lightning Bolt".to_string(), vec![("Cube".to_string(), 1)]),
+            (
+                "Goblin Guide".to_string(),
+                vec![("Boros".to_string(), 2), ("Cube".to_string(), 1)],
+            ),
+        ]);
+
+        let status = CollectionStatus::new(&deck, &owned, &in_decks);
+        assert_eq!(status.get("Lightning Bolt"), Some(&Ownership::Owned));
+        assert_eq!(
+            status.get("Goblin Guide"),
+            Some(&Ownership::InOtherDecks(vec![
+                "Boros".to_string(),
+                "Cube".to_string()
+            ]))
+        );
+        assert_eq!(status.get("Mountain"), None);
+        assert_eq!(status.get("Skullcrack"), None);
+        assert_eq!(status.needed(), vec![("Eidolon of the Great Revel", 1)]);
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::{BTreeSet, HashSet};
+
+use ponder_db::{card::Card, deck::Zone, scryfall::Format};
+
+use super::{CardExt, Deck, validate::Violation};
+
+/// Ways a card lets you have a second commander
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Pairing {
+    Partner,
+    PartnerWith(String),
+    /// Friends forever and the `Partner—` variants only pair within their group
+    Group(String),
+    ChooseBackground,
+    DoctorsCompanion,
+}
+
+fn pairing(card: &Card) -> Option<Pairing> {
+    card.rules_lines().into_iter().find_map(|line| {
+        if line == "Partner" {
+            Some(Pairing::Partner)
+        } else if let Some(name) = line.strip_prefix("Partner with ") {
+            Some(Pairing::PartnerWith(name.to_string()))
+        } else if let Some(group) = line.strip_prefix("Partner—") {
+            Some(Pairing::Group(group.to_string()))
+        } else if line == "Friends forever" {
+            Some(Pairing::Group(line))
+        } else if line == "Choose a Background" {
+            Some(Pairing::ChooseBackground)
+        } else if line == "Doctor's companion" {
+            Some(Pairing::DoctorsCompanion)
+        } else {
+            None
+        }
+    })
+}
+
+fn is_doctor(card: &Card) -> bool {
+    card.front_type_line().contains("Time Lord Doctor")
+}
+
+fn can_pair(first: &Card, second: &Card) -> bool {
+    match (pairing(first), pairing(second)) {
+        (Some(Pairing::Partner), Some(Pairing::Partner)) => true,
+        (Some(Pairing::PartnerWith(a)), Some(Pairing::PartnerWith(b))) => {
+            a == second.name && b == first.name
+        }
+        (Some(Pairing::Group(a)), Some(Pairing::Group(b))) => a == b,
+        (Some(Pairing::ChooseBackground), _) => second.has_subtype("Background"),
+        (_, Some(Pairing::ChooseBackground)) => first.has_subtype("Background"),
+        (Some(Pairing::DoctorsCompanion), _) => is_doctor(second),
+        (_, Some(Pairing::DoctorsCompanion)) => is_doctor(first),
+        _ => false,
+    }
+}
+
+pub fn is_commander_format(format: &Format) -> bool {
+    matches!(
+        format,
+        Format::Commander
+            | Format::Duel
+            | Format::Brawl
+            | Format::StandardBrawl
+            | Format::Oathbreaker
+            | Format::PauperCommander
+            | Format::Predh
+    )
+}
+
+/// Whether a card can lead a deck in a format.
+///
+/// `uncommons` holds the names of cards printed at uncommon, which Pauper Commander needs.
+fn is_eligible(card: &Card, format: &Format, uncommons: &HashSet<String>) -> bool {
+    let legendary_creature = card.is_type("Legendary") && card.is_type("Creature");
+    let says_so = card
+        .rules_lines()
+        .iter()
+        .any(|line| line.contains("can be your commander"));
+
+    match format {
+        Format::Oathbreaker => card.is_type("Planeswalker"),
+        Format::Brawl | Format::StandardBrawl => {
+            legendary_creature
+                || (card.is_type("Legendary") && card.is_type("Planeswalker"))
+                || says_so
+        }
+        Format::PauperCommander => card.is_type("Creature") && uncommons.contains(&card.name),
+        _ => legendary_creature || says_so,
+    }
+}
+
+/// Check commanders, partners and color identity for the commander formats
+pub fn validate_commander(deck: &Deck, uncommons: &HashSet<String>) -> Vec<Violation> {
+    if !is_commander_format(&deck.format) {
+        return vec![];
+    }
+
+    let mut violations = Vec::new();
+    let mut commanders: Vec<&Card> = deck.commanders().map(|entry| &entry.card).collect();
+
+    // Signature spells have their own zone, but decks from before it kept them with the
+    // oathbreaker
+    if deck.format == Format::Oathbreaker {
+        let (oathbreakers, others): (Vec<&Card>, Vec<&Card>) = commanders
+            .into_iter()
+            .partition(|card| card.is_type("Planeswalker"));
+
+        let mut signature_spells = 0;
+        for card in others
+            .into_iter()
+            .chain(deck.entries(Zone::SignatureSpell).map(|entry| &entry.card))
+        {
+            if card.is_type("Instant") || card.is_type("Sorcery") {
+                signature_spells += 1;
+            } else {
+                violations.push(Violation::IneligibleCommander {
+                    card: card.name.clone(),
+                });
+            }
+        }
+
+        if !oathbreakers.is_empty() && signature_spells != oathbreakers.len() {
+            violations.push(Violation::SignatureSpells {
+                count: signature_spells,
+                expected: oathbreakers.len(),
+            });
+        }
+
+        commanders = oathbreakers;
+    }
+
+    match commanders.as_slice() {
+        [] => violations.push(Violation::MissingCommander),
+        [commander] => {
+            if !is_eligible(commander, &deck.format, uncommons) {
+                violations.push(Violation::IneligibleCommander {
+                    card: commander.name.clone(),
+                });
+            }
+        }
+        [first, second] => {
+            if !can_pair(first, second) {
+                violations.push(Violation::InvalidPartners {
+                    first: first.name.clone(),
+                    second: second.name.clone(),
+                });
+            }
+
+            for (commander, other) in [(first, second), (second, first)] {
+                let background = commander.has_subtype("Background")
+                    && pairing(other) == Some(Pairing::ChooseBackground);
+
+                if !background && !is_eligible(commander, &deck.format, uncommons) {
+                    violations.push(Violation::IneligibleCommander {
+                        card: commander.name.clone(),
+                    });
+                }
+            }
+        }
+        _ => violations.push(Violation::TooManyCommanders {
+            count: commanders.len(),
+        }),
+    }
+
+    if commanders.is_empty() {
+        return violations;
+    }
+
+    let identity = commanders
+        .iter()
+        .filter_map(|card| card.color_identity)
+        .fold(0, |acc, bits| acc | bits);
+
+    let outside = deck
+        .cards
+        .iter()
+        .filter(|entry| entry.zone.is_played())
+        .filter(|entry| entry.card.color_identity.unwrap_or_default() & !identity != 0)
+        .map(|entry| entry.card.name.as_str())
+        .collect::<BTreeSet<&str>>();
+
+    violations.extend(
+        outside
+            .into_iter()
+            .map(|card| Violation::OutsideColorIdentity {
+                card: card.to_string(),
+            }),
+    );
+
+    violations
+}
+
+#[cfg(test)]
+mod commander_tests {
+    use super::*;
+    use crate::data::{TestCard, test_card, test_deck};
+    use ponder_db::deck::Zone;
+
+    #[test]
+    fn color_identity() {
+        let krenko = test_card(1, "Krenko, Mob Boss")
+            .with_type_line("Legendary Creature — Goblin Warrior")
+            .with_identity(8);
+        let deck = test_deck(
+            Format::Commander,
+            [
+                (krenko, 1, Zone::Commander),
+                (
+                    test_card(2, "Goblin Guide")
+                        .with_type_line("Creature — Goblin Scout")
+                        .with_text("Haste")
+                        .with_identity(8),
+                    1,
+                    Zone::Main,
+                ),
+                (
+                    test_card(3, "Counterspell")
+                        .with_type_line("Instant")
+                        .with_identity(2),
+                    1,
+                    Zone::Main,
+                ),
+                (
+                    test_card(4, "Sol Ring")
+                        .with_type_line("Artifact")
+                        .with_identity(0),
+                    1,
+                    Zone::Main,
+                ),
+            ],
+        );
+
+        assert_eq!(
+            validate_commander(&deck, &HashSet::new()),
+            vec![Violation::OutsideColorIdentity {
+                card: "Counterspell".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn partners() {
+        let kraum = test_card(1, "Kraum, Ludevic's Opus")
+            .with_type_line("Legendary Creature — Zombie Horror")
+            .with_text("Partner (You can have two commanders if both have partner.)")
+            .with_identity(10);
+        let tymna = test_card(2, "Tymna the Weaver")
+            .with_type_line("Legendary Creature — Human Cleric")
+            .with_text("Partner")
+            .with_identity(5);
+        let background = test_card(3, "Raised by Giants")
+            .with_type_line("Legendary Enchantment — Background")
+            .with_identity(16);
+        let wilson = test_card(4, "Wilson, Refined Grizzly")
+            .with_type_line("Legendary Creature — Bear Warrior")
+            .with_text("Choose a Background")
+            .with_identity(16);
+
+        let pair = |first: &Card, second: &Card| {
+            test_deck(
+                Format::Commander,
+                [first, second].map(|card| (card.clone(), 1, Zone::Commander)),
+            )
+        };
+
+        assert!(validate_commander(&pair(&kraum, &tymna), &HashSet::new()).is_empty());
+        assert!(validate_commander(&pair(&wilson, &background), &HashSet::new()).is_empty());
+        assert_eq!(
+            validate_commander(&pair(&kraum, &background), &HashSet::new()),
+            vec![
+                Violation::InvalidPartners {
+                    first: "Kraum, Ludevic's Opus".to_string(),
+                    second: "Raised by Giants".to_string()
+                },
+                Violation::IneligibleCommander {
+                    card: "Raised by Giants".to_string()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn partner_with() {
+        let pir = test_card(1, "Pir, Imaginative Rascal").with_type_line("Legendary Creature — Human").with_text("Partner with Toothy, Imaginary Friend (When this creature enters, target player may put Toothy into their hand from their library, then shuffle.)").with_identity(2);
+        let toothy = test_card(2, "Toothy, Imaginary Friend")
+            .with_type_line("Legendary Creature — Illusion")
+            .with_text("Partner with Pir, Imaginative Rascal")
+            .with_identity(2);
+        let tymna = test_card(3, "Tymna the Weaver")
+            .with_type_line("Legendary Creature")
+            .with_text("Partner")
+            .with_identity(5);
+
+        assert!(can_pair(&pir, &toothy));
+        assert!(!can_pair(&pir, &tymna));
+    }
+
+    #[test]
+    fn eligibility() {
+        let teferi = test_card(1, "Teferi, Master of Time")
+            .with_type_line("Legendary Planeswalker — Teferi")
+            .with_identity(2);
+        let bear = test_card(2, "Grizzly Bears")
+            .with_type_line("Creature — Bear")
+            .with_identity(16);
+
+        assert!(!is_eligible(&teferi, &Format::Commander, &HashSet::new()));
+        assert!(is_eligible(&teferi, &Format::Brawl, &HashSet::new()));
+        assert!(is_eligible(&teferi, &Format::Oathbreaker, &HashSet::new()));
+
+        let uncommons = HashSet::from(["Grizzly Bears".to_string()]);
+        assert!(is_eligible(&bear, &Format::PauperCommander, &uncommons));
+        assert!(!is_eligible(
+            &bear,
+            &Format::PauperCommander,
+            &HashSet::new()
+        ));
+        assert!(!is_eligible(&bear, &Format::Commander, &uncommons));
+    }
+}
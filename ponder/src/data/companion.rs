@@ -0,0 +1,356 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use ponder_db::{card::Card, deck::Zone};
+
+use super::{CardExt, Deck, validate::FormatRules, validate::Violation};
+
+pub fn is_companion(card: &Card) -> bool {
+    card.rules_lines()
+        .iter()
+        .any(|line| line.starts_with("Companion —"))
+}
+
+/// Keyword abilities that are activated, their `cost: effect` form only being in reminder text
+const ACTIVATED_KEYWORDS: [&str; 14] = [
+    "equip",
+    "crew",
+    "cycling",
+    "reconfigure",
+    "ninjutsu",
+    "fortify",
+    "unearth",
+    "scavenge",
+    "embalm",
+    "eternalize",
+    "level up",
+    "outlast",
+    "transmute",
+    "station",
+];
+
+/// Whether a card has a `cost: effect` ability, including keywords like Equip and Crew.
+///
+/// Basic lands only have their mana ability as reminder text.
+fn has_activated_ability(card: &Card) -> bool {
+    card.is_basic_land()
+        || card.rules_lines().iter().any(|line| {
+            line.contains(':')
+                || line.split(", ").any(|ability| {
+                    let ability = ability.to_lowercase();
+                    // Typecycling such as Islandcycling or Basic landcycling
+                    ability.split(' ').any(|word| word.ends_with("cycling"))
+                        || ACTIVATED_KEYWORDS
+                            .iter()
+                            .any(|keyword| ability.starts_with(keyword))
+                })
+        })
+}
+
+fn mana_value(card: &Card) -> u32 {
+    card.converted_mana_cost as u32
+}
+
+fn mana_symbols(card: &Card) -> Vec<&str> {
+    card.mana_cost
+        .as_deref()
+        .unwrap_or_default()
+        .split(['{', '}'])
+        .filter(|symbol| !symbol.is_empty() && !symbol.trim().is_empty())
+        .collect()
+}
+
+/// Cards breaking a companion's deck building condition.
+///
+/// `None` when the companion isn't one we know the condition for.
+fn offending_cards<'a>(companion: &str, deck: &'a Deck) -> Option<Vec<&'a Card>> {
+    let starting_deck = deck
+        .cards
+        .iter()
+        .filter(|entry| entry.zone.is_deck())
+        .map(|entry| &entry.card)
+        .collect::<Vec<&Card>>();
+
+    let nonland = || starting_deck.iter().filter(|card| !card.is_land());
+    let permanents = || starting_deck.iter().filter(|card| card.is_permanent());
+
+    let offending = match companion {
+        "Gyruda, Doom of Depths" => nonland()
+            .filter(|c| !mana_value(c).is_multiple_of(2))
+            .collect(),
+        "Obosh, the Preypiercer" => nonland()
+            .filter(|c| mana_value(c).is_multiple_of(2))
+            .collect(),
+        "Keruga, the Macrosage" => nonland().filter(|c| mana_value(c) < 3).collect(),
+        "Lurrus of the Dream-Den" => permanents().filter(|c| mana_value(c) > 2).collect(),
+        "Jegantha, the Wellspring" => starting_deck
+            .iter()
+            .filter(|c| {
+                let symbols = mana_symbols(c);
+                symbols.iter().collect::<BTreeSet<_>>().len() != symbols.len()
+            })
+            .collect(),
+        "Kaheera, the Orphanguard" => starting_deck
+            .iter()
+            .filter(|c| c.is_type("Creature"))
+            .filter(|c| {
+                !["Cat", "Elemental", "Nightmare", "Dinosaur", "Beast"]
+                    .iter()
+                    .any(|t| c.has_subtype(t))
+            })
+            .collect(),
+        "Lutri, the Spellchaser" => {
+            // Copies can be in one entry or spread over several printings
+            let mut copies = BTreeMap::new();
+            for entry in deck.cards.iter().filter(|entry| entry.zone.is_deck()) {
+                *copies.entry(entry.card.name.as_str()).or_insert(0) += entry.quantity;
+            }
+            nonland().filter(|c| copies[c.name.as_str()] > 1).collect()
+        }
+        "Umori, the Collector" => {
+            let shared = [
+                "Artifact",
+                "Battle",
+                "Creature",
+                "Enchantment",
+                "Instant",
+                "Kindred",
+                "Planeswalker",
+                "Sorcery",
+            ]
+            .into_iter()
+            .max_by_key(|t| nonland().filter(|c| c.is_type(t)).count())
+            .unwrap_or_default();
+            nonland().filter(|c| !c.is_type(shared)).collect()
+        }
+        "Zirda, the Dawnwaker" => permanents().filter(|c| !has_activated_ability(c)).collect(),
+        "Yorion, Sky Nomad" => vec![],
+        _ => return None,
+    };
+
+    Some(offending.into_iter().copied().collect())
+}
+
+/// Check the deck building condition of any companion in the companion zone or sideboard
+pub fn validate_companion(deck: &Deck) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    for entry in deck
+        .entries(Zone::Companion)
+        .chain(deck.entries(Zone::Sideboard))
+    {
+        if !is_companion(&entry.card) {
+            continue;
+        }
+
+        let companion = entry.card.name.as_str();
+        let Some(offending) = offending_cards(companion, deck) else {
+            continue;
+        };
+
+        let names = offending
+            .iter()
+            .map(|card| card.name.as_str())
+            .collect::<BTreeSet<&str>>();
+
+        violations.extend(
+            names
+                .into_iter()
+                .map(|card| Violation::CompanionRestriction {
+                    companion: companion.to_string(),
+                    card: Some(card.to_string()),
+                }),
+        );
+
+        if companion == "Yorion, Sky Nomad" {
+            let minimum = FormatRules::for_format(&deck.format).min_deck_size + 20;
+            if deck.deck_size() < minimum {
+                violations.push(Violation::CompanionRestriction {
+                    companion: companion.to_string(),
+                    card: None,
+                });
+            }
+        }
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod companion_tests {
+    use super::*;
+    use crate::data::{TestCard, test_card, test_deck};
+    use ponder_db::scryfall::{Finish, Format};
+
+    fn lurrus() -> Card {
+        test_card(1, "Lurrus of the Dream-Den")
+            .with_type_line("Legendary Creature — Cat Nightmare")
+            .with_mana_value(3.0)
+            .with_text("Companion — Each permanent card in your starting deck has mana value 2 or less. (If this card is your chosen companion, you may put it into your hand from outside the game for {3} any time you could cast a sorcery.)\nLifelink")
+    }
+
+    #[test]
+    fn lurrus_restriction() {
+        let deck = test_deck(
+            Format::Modern,
+            [
+                (lurrus(), 4, Zone::Sideboard),
+                (
+                    test_card(2, "Ragavan, Nimble Pilferer")
+                        .with_type_line("Legendary Creature — Monkey Pirate")
+                        .with_mana_value(1.0),
+                    4,
+                    Zone::Main,
+                ),
+                (
+                    test_card(3, "Tarmogoyf")
+                        .with_type_line("Creature — Lhurgoyf")
+                        .with_mana_value(2.0),
+                    4,
+                    Zone::Main,
+                ),
+                (
+                    test_card(4, "Wrenn and Six")
+                        .with_type_line("Legendary Planeswalker — Wrenn")
+                        .with_mana_value(2.0),
+                    4,
+                    Zone::Main,
+                ),
+                (
+                    test_card(5, "Thrun, Breaker of Silence")
+                        .with_type_line("Legendary Creature — Troll Shaman")
+                        .with_mana_value(3.0),
+                    4,
+                    Zone::Main,
+                ),
+                (
+                    test_card(6, "Prismatic Ending")
+                        .with_type_line("Sorcery")
+                        .with_mana_value(3.0),
+                    4,
+                    Zone::Main,
+                ),
+                (
+                    test_card(7, "Forest")
+                        .with_type_line("Basic Land — Forest")
+                        .with_mana_value(0.0),
+                    4,
+                    Zone::Main,
+                ),
+            ],
+        );
+
+        assert_eq!(
+            validate_companion(&deck),
+            vec![Violation::CompanionRestriction {
+                companion: "Lurrus of the Dream-Den".to_string(),
+                card: Some("Thrun, Breaker of Silence".to_string())
+            }]
+        );
+    }
+
+    #[test]
+    fn lutri_restriction() {
+        let lutri = test_card(1, "Lutri, the Spellchaser")
+            .with_type_line("Legendary Creature — Elemental Otter")
+            .with_mana_value(3.0)
+            .with_text("Companion — Each nonland card in your starting deck has a different name.");
+
+        let mut deck = Deck::new("Test", Format::Modern);
+        deck.add(lutri, 1, Zone::Companion, Finish::Nonfoil);
+        deck.add(
+            test_card(2, "Lightning Bolt")
+                .with_type_line("Instant")
+                .with_mana_value(1.0),
+            4,
+            Zone::Main,
+            Finish::Nonfoil,
+        );
+        deck.add(
+            test_card(3, "Counterspell")
+                .with_type_line("Instant")
+                .with_mana_value(2.0),
+            1,
+            Zone::Main,
+            Finish::Nonfoil,
+        );
+        deck.add(
+            test_card(4, "Brainstorm")
+                .with_type_line("Instant")
+                .with_mana_value(1.0),
+            1,
+            Zone::Main,
+            Finish::Nonfoil,
+        );
+        deck.add(
+            test_card(5, "Brainstorm")
+                .with_type_line("Instant")
+                .with_mana_value(1.0),
+            1,
+            Zone::Main,
+            Finish::Nonfoil,
+        );
+        deck.add(
+            test_card(6, "Island")
+                .with_type_line("Basic Land — Island")
+                .with_mana_value(0.0),
+            10,
+            Zone::Main,
+            Finish::Nonfoil,
+        );
+
+        let flagged = validate_companion(&deck)
+            .into_iter()
+            .filter_map(|violation| violation.card().map(str::to_string))
+            .collect::<Vec<_>>();
+        assert_eq!(flagged, vec!["Brainstorm", "Lightning Bolt"]);
+    }
+
+    #[test]
+    fn zirda_restriction() {
+        let permanent = |id, name, type_line, text| {
+            (
+                test_card(id, name)
+                    .with_type_line(type_line)
+                    .with_text(text),
+                1,
+                Zone::Main,
+            )
+        };
+
+        let deck = test_deck(
+            Format::Commander,
+            [
+                (
+                    test_card(1, "Zirda, the Dawnwaker")
+                        .with_type_line("Legendary Creature — Elemental Fox")
+                        .with_text("Companion — Each permanent card in your starting deck has an activated ability."),
+                    1,
+                    Zone::Companion,
+                ),
+                permanent(2, "Bonesplitter", "Artifact — Equipment", "Equipped creature gets +2/+0.\nEquip {1} ({1}: Attach to target creature you control. Equip only as a sorcery.)"),
+                permanent(3, "Smuggler's Copter", "Artifact — Vehicle", "Flying\nWhenever this Vehicle attacks or blocks, you may draw a card. If you do, discard a card.\nCrew 1 (Tap any number of creatures you control with total power 1 or more: This Vehicle becomes an artifact creature until end of turn.)"),
+                permanent(4, "Street Wraith", "Creature — Wraith", "Swampwalk\nCycling—Pay 2 life. (Pay 2 life, Discard this card: Draw a card.)"),
+                permanent(5, "Llanowar Elves", "Creature — Elf Druid", "{T}: Add {G}."),
+                permanent(6, "Forest", "Basic Land — Forest", "({T}: Add {G}.)"),
+                permanent(7, "Grizzly Bears", "Creature — Bear", ""),
+                permanent(8, "Glorious Anthem", "Enchantment", "Creatures you control get +1/+1."),
+            ],
+        );
+
+        let flagged = validate_companion(&deck)
+            .into_iter()
+            .filter_map(|violation| violation.card().map(str::to_string))
+            .collect::<Vec<_>>();
+        assert_eq!(flagged, vec!["Glorious Anthem", "Grizzly Bears"]);
+    }
+
+    #[test]
+    fn companion_detection() {
+        assert!(is_companion(&lurrus()));
+        assert!(!is_companion(
+            &test_card(2, "Tarmogoyf")
+                .with_type_line("Creature — Lhurgoyf")
+                .with_mana_value(2.0)
+        ));
+    }
+}
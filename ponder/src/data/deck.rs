@@ -1,15 +1,176 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
 use ponder_db::{
+    SqliteStore,
     card::Card,
-    scryfall::{Color, Format},
+    deck::{EntryRecord, StoredDeck, Zone},
+    scryfall::{Color, Finish, Format},
 };
 
-pub type DeckEntry = (Card, u8);
+#[derive(Debug, Clone)]
+pub struct DeckEntry {
+    pub card: Card,
+    pub quantity: u32,
+    pub zone: Zone,
+    pub finish: Finish,
+    pub tags: Vec<String>,
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Deck {
+    pub id: Option<i64>,
     pub format: Format,
     pub name: String,
-    pub commander: Option<String>,
     pub colors: Vec<Color>,
     pub cards: Vec<DeckEntry>,
 }
+
+impl Deck {
+    pub fn new(name: impl Into<String>, format: Format) -> Self {
+        Self {
+            id: None,
+            format,
+            name: name.into(),
+            colors: Vec::new(),
+            cards: Vec::new(),
+        }
+    }
+
+    /// Add copies of a card, merging with an existing entry for the same printing and finish
+    pub fn add(&mut self, card: Card, quantity: u32, zone: Zone, finish: Finish) -> &mut DeckEntry {
+        let index = match self.cards.iter().position(|entry| {
+            entry.card.card_id == card.card_id && entry.zone == zone && entry.finish == finish
+        }) {
+            Some(index) => {
+                let entry = &mut self.cards[index];
+                entry.quantity = entry.quantity.saturating_add(quantity);
+                index
+            }
+            None => {
+                self.cards.push(DeckEntry {
+                    card,
+                    quantity,
+                    zone,
+                    finish,
+                    tags: Vec::new(),
+                });
+                self.cards.len() - 1
+            }
+        };
+
+        &mut self.cards[index]
+    }
+
+    /// Entries of a zone grouped under each of their tags, untagged entries last under `None`.
+    ///
+    /// Entries with several tags appear in each group.
+    pub fn by_tag(&self, zone: Zone) -> Vec<(Option<&str>, Vec<&DeckEntry>)> {
+        let mut groups: BTreeMap<Option<&str>, Vec<&DeckEntry>> = BTreeMap::new();
+        for entry in self.entries(zone) {
+            if entry.tags.is_empty() {
+                groups.entry(None).or_default().push(entry);
+            }
+
+            for tag in entry.tags.iter() {
+                groups.entry(Some(tag.as_str())).or_default().push(entry);
+            }
+        }
+
+        let mut groups = groups.into_iter().collect::<Vec<_>>();
+        let untagged = groups.iter().filter(|(tag, _)| tag.is_none()).count();
+        groups.rotate_left(untagged);
+        groups
+    }
+
+    /// Write the deck and its entries to the database, creating it if it's new.
+    ///
+    /// Every save is kept as a revision, returns the revision id.
+    pub async fn save(&mut self, store: &SqliteStore, note: Option<&str>) -> Result<i64> {
+        let id = match self.id {
+            Some(id) => {
+                store.rename_deck(id, &self.name).await?;
+                id
+            }
+            None => store.create_deck(&self.name, &self.format).await?,
+        };
+
+        let entries = self
+            .cards
+            .iter()
+            .map(|entry| EntryRecord {
+                card_id: entry.card.card_id.clone(),
+                zone: entry.zone,
+                quantity: entry.quantity,
+                finish: entry.finish.clone(),
+                tags: entry.tags.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        let revision = store.save_deck(id, &entries, note).await?;
+        self.id = Some(id);
+        Ok(revision)
+    }
+
+    pub fn entries(&self, zone: Zone) -> impl Iterator<Item = &DeckEntry> {
+        self.cards.iter().filter(move |entry| entry.zone == zone)
+    }
+
+    pub fn commanders(&self) -> impl Iterator<Item = &DeckEntry> {
+        self.entries(Zone::Commander)
+    }
+
+    /// Total number of cards of an entry type, counting every copy
+    pub fn count(&self, zone: Zone) -> usize {
+        self.entries(zone)
+            .map(|entry| entry.quantity as usize)
+            .sum()
+    }
+
+    /// Number of cards in the zones that make up the deck, including commanders
+    pub fn deck_size(&self) -> usize {
+        self.cards
+            .iter()
+            .filter(|entry| entry.zone.is_deck())
+            .map(|entry| entry.quantity as usize)
+            .sum()
+    }
+
+    /// Color identity of every card as a bitmask
+    pub fn color_identity(&self) -> u8 {
+        self.cards
+            .iter()
+            .filter_map(|entry| entry.card.color_identity)
+            .fold(0, |acc, bits| acc | bits)
+    }
+
+    /// Recalculate the deck colors from its cards' color identities
+    pub fn update_colors(&mut self) {
+        self.colors = Color::from_bits(self.color_identity());
+    }
+}
+
+impl From<StoredDeck> for Deck {
+    fn from(stored: StoredDeck) -> Self {
+        let mut deck = Self {
+            id: Some(stored.id),
+            format: stored.format,
+            name: stored.name,
+            colors: Vec::new(),
+            cards: stored
+                .entries
+                .into_iter()
+                .map(|entry| DeckEntry {
+                    card: entry.card,
+                    quantity: entry.quantity,
+                    zone: entry.zone,
+                    finish: entry.finish,
+                    tags: entry.tags,
+                })
+                .collect(),
+        };
+
+        deck.update_colors();
+        deck
+    }
+}
@@ -0,0 +1,734 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::Result;
+use ponder_db::{
+    SqliteStore,
+    card::Card,
+    deck::Zone,
+    scryfall::{Color, Format},
+    search::CardSearch,
+};
+use rand::{SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
+
+use super::{CardExt, Deck, DeckEntry, probability::HAND_SIZE, stats, validate::FormatRules};
+
+/// Games played when no count is given
+pub const DEFAULT_GAMES: u32 = 10_000;
+
+/// Spells costing more than this aren't simulated
+const MAX_TURN: u32 = 10;
+
+const BASIC_TYPES: [(&str, Color); 5] = [
+    ("Plains", Color::White),
+    ("Island", Color::Blue),
+    ("Swamp", Color::Black),
+    ("Mountain", Color::Red),
+    ("Forest", Color::Green),
+];
+
+/// How a land helps cast spells
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LandModel {
+    /// Colors as a bitmask, in the same layout as the database
+    pub colors: u8,
+    pub enters_tapped: bool,
+}
+
+/// Work out the colors a land makes and whether it comes in tapped.
+///
+/// Fetch lands count as every color they can find, `basic_colors` being the colors of the
+/// deck's basics for lands that fetch any basic.
+pub fn land_model(card: &Card, basic_colors: u8) -> LandModel {
+    let mut colors = card.produced_mana.unwrap_or_default();
+    for (land_type, color) in BASIC_TYPES {
+        if card.has_subtype(land_type) {
+            colors |= color as u8;
+        }
+    }
+
+    let mut enters_tapped = false;
+    for line in card.rules_lines() {
+        if line.to_lowercase().contains("search your library for") {
+            for (land_type, color) in BASIC_TYPES {
+                if line.contains(land_type) {
+                    colors |= color as u8;
+                }
+            }
+            if line.contains("basic land card") {
+                colors |= basic_colors;
+            }
+        }
+
+        let conditional = line.contains("unless") || line.contains("If you don't");
+        let tapped = line.contains("enters tapped")
+            || line.contains("enters the battlefield tapped")
+            || line.contains("onto the battlefield tapped");
+        if tapped && !conditional {
+            enters_tapped = true;
+        }
+    }
+
+    LandModel {
+        colors: colors & !(Color::Tap as u8),
+        enters_tapped,
+    }
+}
+
+/// Colored mana a spell needs, ignoring hybrid and Phyrexian symbols that can be paid other ways
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Requirement {
+    pub mana_value: u32,
+    /// Pips needed of each color, indexed in WUBRG order
+    pub pips: [u32; 5],
+}
+
+impl Requirement {
+    pub fn new(card: &Card) -> Self {
+        let mut pips = [0; 5];
+        let cost = card.mana_cost.as_deref().unwrap_or_default();
+        for symbol in cost.split('{').filter_map(|s| s.strip_suffix('}')) {
+            if let Some(i) = ["W", "U", "B", "R", "G"].iter().position(|c| *c == symbol) {
+                pips[i] += 1;
+            }
+        }
+
+        Self {
+            mana_value: card.converted_mana_cost as u32,
+            pips,
+        }
+    }
+
+    /// Whether the lands can pay for the spell, one mana each.
+    ///
+    /// By Hall's theorem the pips can be paid when every group of colors has at least as many
+    /// lands making one of them as it has pips.
+    pub fn castable(&self, lands: &[u8]) -> bool {
+        if (lands.len() as u32) < self.mana_value {
+            return false;
+        }
+
+        (1u8..32).all(|subset| {
+            let (needed, mask) =
+                Color::WUBRG
+                    .iter()
+                    .enumerate()
+                    .fold((0, 0u8), |(needed, mask), (i, color)| {
+                        if subset & (1 << i) == 0 {
+                            (needed, mask)
+                        } else {
+                            (needed + self.pips[i], mask | *color as u8)
+                        }
+                    });
+
+            needed == 0 || lands.iter().filter(|land| *land & mask != 0).count() as u32 >= needed
+        })
+    }
+}
+
+/// Colored sources needed to cast a spell on curve 90% of the time, from Frank Karsten's
+/// tables for 40, 60 and 99 card decks. Rows are pips, columns the generic mana on top.
+pub fn karsten_sources(deck_size: usize, pips: u32, mana_value: u32) -> Option<u32> {
+    const LIMITED: [[u32; 5]; 4] = [
+        [9, 9, 8, 7, 6],
+        [14, 13, 12, 11, 10],
+        [16, 15, 14, 13, 12],
+        [17, 17, 16, 15, 14],
+    ];
+    const CONSTRUCTED: [[u32; 5]; 4] = [
+        [14, 13, 12, 10, 9],
+        [20, 19, 18, 16, 15],
+        [23, 22, 21, 19, 18],
+        [24, 24, 23, 22, 21],
+    ];
+    const COMMANDER: [[u32; 5]; 4] = [
+        [19, 19, 18, 16, 15],
+        [30, 28, 26, 23, 22],
+        [36, 33, 30, 28, 26],
+        [40, 39, 36, 33, 31],
+    ];
+
+    if pips == 0 {
+        return None;
+    }
+
+    let table = match deck_size {
+        0..=50 => &LIMITED,
+        51..=80 => &CONSTRUCTED,
+        _ => &COMMANDER,
+    };
+    let row = (pips.min(4) - 1) as usize;
+    let column = mana_value.saturating_sub(pips).min(4) as usize;
+    Some(table[row][column])
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Castability {
+    pub name: String,
+    pub mana_value: u32,
+    /// Share of games with the mana to cast it on the turn matching its mana value
+    pub on_curve: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorSources {
+    pub color: Color,
+    /// Lands that can make the color, counting every copy
+    pub sources: u32,
+    /// Sources the most demanding spell of the color wants
+    pub needed: u32,
+    pub card: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManaBaseReport {
+    pub games: u32,
+    pub seed: u64,
+    /// Hardest spells to cast on curve first
+    pub spells: Vec<Castability>,
+    /// In WUBRG order
+    pub colors: Vec<ColorSources>,
+}
+
+impl ColorSources {
+    /// Fewer sources than the most demanding spell wants
+    pub fn is_short(&self) -> bool {
+        self.sources < self.needed
+    }
+}
+
+/// Pick the land to play from hand, preferring untapped lands and then new colors
+fn choose_land(hand: &[LandModel], battlefield: &[(LandModel, u32)]) -> Option<usize> {
+    let have = battlefield
+        .iter()
+        .fold(0, |acc, (land, _)| acc | land.colors);
+    (0..hand.len()).max_by_key(|i| {
+        let land = hand[*i];
+        (
+            !land.enters_tapped,
+            (land.colors & !have).count_ones(),
+            land.colors.count_ones(),
+        )
+    })
+}
+
+/// Play out seeded games of a deck, laying a land a turn, to see how often each spell can be
+/// cast on curve and compare the colored sources against Karsten's targets
+pub fn analyze(deck: &Deck, games: u32, seed: u64) -> ManaBaseReport {
+    let basic_colors = deck
+        .entries(Zone::Main)
+        .filter(|entry| entry.card.is_basic_land())
+        .fold(0, |acc, entry| acc | land_model(&entry.card, 0).colors);
+
+    let mut library = Vec::new();
+    let mut sources: BTreeMap<Color, u32> = BTreeMap::new();
+    for entry in deck.entries(Zone::Main) {
+        let land = entry
+            .card
+            .is_land()
+            .then(|| land_model(&entry.card, basic_colors));
+        for color in Color::from_bits(land.map(|land| land.colors).unwrap_or_default()) {
+            *sources.entry(color).or_default() += entry.quantity;
+        }
+        library.extend(std::iter::repeat_n(land, entry.quantity as usize));
+    }
+
+    let mut spells: BTreeMap<String, Requirement> = BTreeMap::new();
+    for entry in deck.cards.iter() {
+        let cast = matches!(
+            entry.zone,
+            Zone::Main | Zone::Commander | Zone::SignatureSpell | Zone::Companion
+        );
+        if cast && !entry.card.is_land() {
+            spells.insert(entry.card.name.clone(), Requirement::new(&entry.card));
+        }
+    }
+
+    let mut requirements = spells
+        .values()
+        .filter(|req| req.mana_value <= MAX_TURN)
+        .cloned()
+        .collect::<Vec<_>>();
+    requirements.sort();
+    requirements.dedup();
+    let last_turn = requirements
+        .iter()
+        .map(|req| req.mana_value.max(1))
+        .max()
+        .unwrap_or(1);
+
+    let mut castable: HashMap<&Requirement, u32> = HashMap::new();
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    for _ in 0..games {
+        library.shuffle(&mut rng);
+        let mut cards = library.iter();
+        let mut hand = cards
+            .by_ref()
+            .take(HAND_SIZE as usize)
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        let mut battlefield: Vec<(LandModel, u32)> = Vec::new();
+
+        for turn in 1..=last_turn {
+            if turn > 1
+                && let Some(Some(land)) = cards.next()
+            {
+                hand.push(*land);
+            }
+
+            if let Some(i) = choose_land(&hand, &battlefield) {
+                let land = hand.remove(i);
+                let ready = if land.enters_tapped { turn + 1 } else { turn };
+                battlefield.push((land, ready));
+            }
+
+            let untapped = battlefield
+                .iter()
+                .filter(|(_, ready)| *ready <= turn)
+                .map(|(land, _)| land.colors)
+                .collect::<Vec<_>>();
+            for req in requirements
+                .iter()
+                .filter(|req| req.mana_value.max(1) == turn)
+            {
+                if req.castable(&untapped) {
+                    *castable.entry(req).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    let mut spell_odds = spells
+        .iter()
+        .filter(|(_, req)| req.mana_value <= MAX_TURN)
+        .map(|(name, req)| Castability {
+            name: name.clone(),
+            mana_value: req.mana_value,
+            on_curve: castable.get(req).copied().unwrap_or_default() as f64 / games.max(1) as f64,
+        })
+        .collect::<Vec<_>>();
+    spell_odds.sort_by(|a, b| a.on_curve.total_cmp(&b.on_curve).then(a.name.cmp(&b.name)));
+
+    let deck_size = deck.deck_size();
+    let colors = Color::WUBRG
+        .iter()
+        .enumerate()
+        .filter_map(|(i, color)| {
+            let (card, needed) = spells
+                .iter()
+                .filter_map(|(name, req)| {
+                    karsten_sources(deck_size, req.pips[i], req.mana_value).map(|n| (name, n))
+                })
+                .max_by_key(|(_, needed)| *needed)?;
+
+            Some(ColorSources {
+                color: *color,
+                sources: sources.get(color).copied().unwrap_or_default(),
+                needed,
+                card: card.clone(),
+            })
+        })
+        .collect();
+
+    ManaBaseReport {
+        games,
+        seed,
+        spells: spell_odds,
+        colors,
+    }
+}
+
+/// Name of the basic land making a color
+pub fn basic_land_name(color: Color) -> &'static str {
+    BASIC_TYPES
+        .iter()
+        .find(|(_, c)| *c == color)
+        .map_or("Wastes", |(name, _)| name)
+}
+
+/// Whether a card ramps: tagged `ramp`, a nonland card that makes mana, or one that puts lands
+/// onto the battlefield
+pub fn is_ramp(entry: &DeckEntry) -> bool {
+    if entry
+        .tags
+        .iter()
+        .any(|tag| tag.eq_ignore_ascii_case("ramp"))
+    {
+        return true;
+    }
+
+    !entry.card.is_land()
+        && (entry.card.produced_mana.is_some_and(|colors| colors > 0)
+            || entry
+                .card
+                .rules_lines()
+                .iter()
+                .any(|line| line.contains("land card") && line.contains("onto the battlefield")))
+}
+
+/// Lands to play from Karsten's regression on the average mana value of the nonland cards and
+/// the number of ramp cards
+pub fn recommended_lands(format: &Format, average_mana_value: f32, ramp: u32) -> u32 {
+    let ramp = ramp as f32;
+    let lands = if FormatRules::for_format(format).min_deck_size >= 100 {
+        31.42 + 3.13 * average_mana_value - 0.28 * ramp
+    } else {
+        19.59 + 1.90 * average_mana_value - 0.28 * ramp
+    };
+
+    lands.round().max(0.0) as u32
+}
+
+/// A suggested land base for a deck
+#[derive(Debug, Clone)]
+pub struct LandSuggestion {
+    pub lands: u32,
+    pub average_mana_value: f32,
+    pub ramp: u32,
+    /// Basics split by the pips of each color
+    pub basics: Vec<(Color, u32)>,
+    /// Lands making two or more of the deck's colors, with the copies of each
+    pub nonbasics: Vec<(Card, u32)>,
+}
+
+impl LandSuggestion {
+    /// Work out a land base from the deck's spells, picking nonbasics from `candidates`
+    pub fn new(deck: &Deck, candidates: Vec<Card>) -> Self {
+        let identity = deck.color_identity();
+        let spells = deck.cards.iter().filter(|entry| {
+            matches!(entry.zone, Zone::Main | Zone::Commander) && !entry.card.is_land()
+        });
+
+        let mut pips: BTreeMap<Color, u32> = BTreeMap::new();
+        let (mut total_mana_value, mut count, mut ramp) = (0.0, 0, 0);
+        for entry in spells {
+            let quantity = entry.quantity;
+            total_mana_value += entry.card.converted_mana_cost * quantity as f32;
+            count += quantity;
+            if is_ramp(entry) {
+                ramp += quantity;
+            }
+            let cost = entry.card.mana_cost.as_deref().unwrap_or_default();
+            for (color, n) in stats::pips(cost) {
+                *pips.entry(color).or_default() += n as u32 * quantity;
+            }
+        }
+
+        let average_mana_value = if count > 0 {
+            total_mana_value / count as f32
+        } else {
+            0.0
+        };
+        let lands = recommended_lands(&deck.format, average_mana_value, ramp);
+
+        let colors = Color::from_bits(identity);
+        let mut nonbasics = Vec::new();
+        if colors.len() > 1 {
+            let copies = FormatRules::for_format(&deck.format).max_copies.min(4) as u32;
+            let mut seen = HashSet::new();
+            let mut candidates = candidates
+                .into_iter()
+                .filter(|card| !card.is_basic_land() && seen.insert(card.name.clone()))
+                .map(|card| (land_model(&card, identity), card))
+                .filter(|(land, _)| (land.colors & identity).count_ones() > 1)
+                .collect::<Vec<_>>();
+            candidates.sort_by_key(|(land, card)| {
+                (
+                    land.enters_tapped,
+                    std::cmp::Reverse((land.colors & identity).count_ones()),
+                    card.edhrec_rank.unwrap_or(i32::MAX),
+                )
+            });
+
+            let mut slots = lands / 2;
+            for (_, card) in candidates {
+                if slots == 0 {
+                    break;
+                }
+                let copies = copies.min(slots);
+                slots -= copies;
+                nonbasics.push((card, copies));
+            }
+        }
+
+        let remaining = lands - nonbasics.iter().map(|(_, n)| n).sum::<u32>();
+        let weights = colors
+            .iter()
+            .map(|color| pips.get(color).copied().unwrap_or_default())
+            .collect::<Vec<_>>();
+        let basics = colors
+            .into_iter()
+            .zip(split(remaining, &weights))
+            .filter(|(_, n)| *n > 0)
+            .collect();
+
+        Self {
+            lands,
+            average_mana_value,
+            ramp,
+            basics,
+            nonbasics,
+        }
+    }
+}
+
+/// Split a total by weights, handing what's left after rounding down to the largest remainders.
+/// With no weight at all it's split evenly.
+fn split(total: u32, weights: &[u32]) -> Vec<u32> {
+    let weights = if weights.iter().all(|w| *w == 0) {
+        vec![1; weights.len()]
+    } else {
+        weights.to_vec()
+    };
+    let sum = weights.iter().sum::<u32>();
+    if sum == 0 {
+        return Vec::new();
+    }
+
+    let mut shares = weights.iter().map(|w| total * w / sum).collect::<Vec<_>>();
+    let mut order = (0..weights.len()).collect::<Vec<_>>();
+    order.sort_by_key(|i| std::cmp::Reverse(total * weights[*i] % sum));
+    let left = total - shares.iter().sum::<u32>();
+    for i in order.into_iter().take(left as usize) {
+        shares[i] += 1;
+    }
+
+    shares
+}
+
+impl Deck {
+    /// Suggest a land count and land base from format legal lands in the deck's colors
+    pub async fn suggest_lands(
+        &self,
+        store: &SqliteStore,
+        exclude_reserved: bool,
+    ) -> Result<LandSuggestion> {
+        let identity = self.color_identity();
+
+        let mut search = CardSearch::new()
+            .type_line("Land")
+            .legal_in(self.format.clone())
+            .produces_within(identity);
+        if exclude_reserved {
+            search = search.exclude_reserved();
+        }
+
+        Ok(LandSuggestion::new(self, store.search(&search).await?))
+    }
+}
+
+#[cfg(test)]
+mod manabase_tests {
+    use super::*;
+    use crate::data::{TestCard, test_card};
+    use ponder_db::scryfall::{Finish, Format};
+
+    #[test]
+    fn land_models() {
+        let w = Color::White as u8;
+        let u = Color::Blue as u8;
+        let r = Color::Red as u8;
+
+        let shock = test_card(1, "Hallowed Fountain").with_type_line("Land — Plains Island").with_text("({T}: Add {W} or {U}.)\nAs Hallowed Fountain enters, you may pay 2 life. If you don't, it enters tapped.").with_mana_cost("").with_produced_mana(w | u);
+        assert_eq!(
+            land_model(&shock, 0),
+            LandModel {
+                colors: w | u,
+                enters_tapped: false
+            }
+        );
+
+        let tapland = test_card(2, "Tranquil Cove").with_type_line("Land").with_text("Tranquil Cove enters tapped.\nWhen Tranquil Cove enters, you gain 1 life.\n{T}: Add {W} or {U}.").with_mana_cost("").with_produced_mana(w | u);
+        assert!(land_model(&tapland, 0).enters_tapped);
+
+        let fetch = test_card(3, "Flooded Strand").with_type_line("Land").with_text("{T}, Pay 1 life, Sacrifice Flooded Strand: Search your library for a Plains or Island card, put it onto the battlefield, then shuffle.").with_mana_cost("").with_produced_mana(0);
+        assert_eq!(
+            land_model(&fetch, 0),
+            LandModel {
+                colors: w | u,
+                enters_tapped: false
+            }
+        );
+
+        let wilds = test_card(4, "Evolving Wilds").with_type_line("Land").with_text("{T}, Sacrifice Evolving Wilds: Search your library for a basic land card, put it onto the battlefield tapped, then shuffle.").with_mana_cost("").with_produced_mana(0);
+        assert_eq!(
+            land_model(&wilds, r),
+            LandModel {
+                colors: r,
+                enters_tapped: true
+            }
+        );
+    }
+
+    #[test]
+    fn castable_lands() {
+        let w = Color::White as u8;
+        let u = Color::Blue as u8;
+        let card = test_card(1, "Absorb")
+            .with_type_line("Instant")
+            .with_mana_cost("{W}{U}{U}")
+            .with_produced_mana(0)
+            .with_mana_value(3.0);
+        let absorb = Requirement::new(&card);
+        assert_eq!(
+            absorb,
+            Requirement {
+                mana_value: 3,
+                pips: [1, 2, 0, 0, 0],
+            }
+        );
+
+        assert!(absorb.castable(&[w | u, u, u]));
+        assert!(absorb.castable(&[w, u, u | w]));
+        assert!(!absorb.castable(&[w, w, u]));
+        assert!(!absorb.castable(&[w | u, u]));
+
+        let wrath = Requirement {
+            mana_value: 4,
+            pips: [2, 0, 0, 0, 0],
+        };
+        assert!(wrath.castable(&[w, w, 0, u]));
+        assert!(!wrath.castable(&[w, u | Color::Black as u8, 0, u]));
+    }
+
+    #[test]
+    fn karsten_targets() {
+        assert_eq!(karsten_sources(60, 1, 1), Some(14));
+        assert_eq!(karsten_sources(60, 2, 4), Some(18));
+        assert_eq!(karsten_sources(100, 1, 3), Some(18));
+        assert_eq!(karsten_sources(40, 3, 9), Some(12));
+        assert_eq!(karsten_sources(60, 0, 3), None);
+    }
+
+    #[test]
+    fn simulate() {
+        let mut deck = Deck::new("Boros", Format::Modern);
+        let r = Color::Red as u8;
+        deck.add(
+            test_card(1, "Mountain")
+                .with_type_line("Basic Land — Mountain")
+                .with_mana_cost("")
+                .with_produced_mana(r),
+            20,
+            Zone::Main,
+            Finish::Nonfoil,
+        );
+        deck.add(
+            test_card(2, "Plains")
+                .with_type_line("Basic Land — Plains")
+                .with_mana_cost("")
+                .with_produced_mana(Color::White as u8),
+            4,
+            Zone::Main,
+            Finish::Nonfoil,
+        );
+        let bolt = test_card(3, "Lightning Bolt")
+            .with_type_line("Instant")
+            .with_mana_cost("{R}")
+            .with_produced_mana(0)
+            .with_mana_value(1.0);
+        deck.add(bolt, 32, Zone::Main, Finish::Nonfoil);
+        let wrath = test_card(4, "Wrath of God")
+            .with_type_line("Sorcery")
+            .with_mana_cost("{2}{W}{W}")
+            .with_produced_mana(0)
+            .with_mana_value(4.0);
+        deck.add(wrath, 4, Zone::Main, Finish::Nonfoil);
+
+        let report = analyze(&deck, 2_000, 7);
+        assert_eq!(report, analyze(&deck, 2_000, 7));
+        assert_eq!(report.spells[0].name, "Wrath of God");
+        assert!(report.spells[0].on_curve < 0.2);
+        assert!(report.spells[1].on_curve > 0.8);
+
+        assert_eq!(
+            report.colors,
+            vec![
+                ColorSources {
+                    color: Color::White,
+                    sources: 4,
+                    needed: 18,
+                    card: "Wrath of God".to_string()
+                },
+                ColorSources {
+                    color: Color::Red,
+                    sources: 20,
+                    needed: 14,
+                    card: "Lightning Bolt".to_string()
+                },
+            ]
+        );
+        assert!(report.colors[0].is_short() && !report.colors[1].is_short());
+    }
+
+    #[test]
+    fn land_counts() {
+        assert_eq!(recommended_lands(&Format::Modern, 2.0, 0), 23);
+        assert_eq!(recommended_lands(&Format::Modern, 2.0, 8), 21);
+        assert_eq!(recommended_lands(&Format::Commander, 3.2, 10), 39);
+        assert_eq!(split(17, &[3, 1]), vec![13, 4]);
+        assert_eq!(split(10, &[1, 1, 1]), vec![4, 3, 3]);
+        assert_eq!(split(9, &[0, 0]), vec![5, 4]);
+    }
+
+    #[test]
+    fn suggest_lands() {
+        let w = Color::White as u8;
+        let u = Color::Blue as u8;
+        let b = Color::Black as u8;
+        let mut deck = Deck::new("Azorius", Format::Modern);
+        for (id, name, cost, identity, quantity) in [
+            (1, "Absorb", "{W}{U}{U}", w | u, 20),
+            (2, "Swords to Plowshares", "{W}", w, 16),
+        ] {
+            let spell = test_card(id, name)
+                .with_type_line("Instant")
+                .with_mana_cost(cost)
+                .with_produced_mana(0)
+                .with_mana_value(cost.matches('{').count() as f32)
+                .with_identity(identity);
+            deck.add(spell, quantity, Zone::Main, Finish::Nonfoil);
+        }
+
+        let candidates = vec![
+            test_card(10, "Azorius Chancery")
+                .with_type_line("Land")
+                .with_text("Azorius Chancery enters tapped.")
+                .with_mana_cost("")
+                .with_produced_mana(w | u),
+            test_card(11, "Hallowed Fountain")
+                .with_type_line("Land — Plains Island")
+                .with_mana_cost("")
+                .with_produced_mana(w | u),
+            test_card(12, "Hallowed Fountain")
+                .with_type_line("Land — Plains Island")
+                .with_mana_cost("")
+                .with_produced_mana(w | u),
+            test_card(13, "Plains")
+                .with_type_line("Basic Land — Plains")
+                .with_mana_cost("")
+                .with_produced_mana(w),
+            test_card(14, "Caves of Koilos")
+                .with_type_line("Land")
+                .with_mana_cost("")
+                .with_produced_mana(w | b),
+        ];
+        let suggestion = LandSuggestion::new(&deck, candidates);
+
+        // 36 spells averaging 2.11 mana
+        assert_eq!(suggestion.lands, 24);
+        assert_eq!(suggestion.ramp, 0);
+        let nonbasics = suggestion
+            .nonbasics
+            .iter()
+            .map(|(card, n)| (card.name.as_str(), *n))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            nonbasics,
+            vec![("Hallowed Fountain", 4), ("Azorius Chancery", 4)]
+        );
+        // 36 white and 40 blue pips
+        assert_eq!(suggestion.basics, vec![(Color::White, 8), (Color::Blue, 8)]);
+    }
+}
@@ -1,2 +1,83 @@
+pub mod bracket;
+pub mod card;
+pub mod collection;
+pub mod commander;
+pub mod companion;
 pub mod deck;
+pub mod manabase;
+pub mod playtest;
+pub mod price;
+pub mod probability;
+pub mod stats;
+pub mod validate;
+pub use card::CardExt;
 pub use deck::*;
+
+#[cfg(test)]
+pub(crate) fn test_card(id: i32, name: &str) -> ponder_db::card::Card {
+    ponder_db::card::Card {
+        id,
+        card_id: format!("card-{id}"),
+        object: "card".to_string(),
+        name: name.to_string(),
+        ..Default::default()
+    }
+}
+
+/// Setters for the card fields tests look at, chained onto [`test_card`]
+#[cfg(test)]
+pub(crate) trait TestCard {
+    fn with_type_line(self, type_line: &str) -> Self;
+    fn with_text(self, text: &str) -> Self;
+    fn with_identity(self, identity: u8) -> Self;
+    fn with_mana_value(self, mana_value: f32) -> Self;
+    fn with_mana_cost(self, mana_cost: &str) -> Self;
+    fn with_produced_mana(self, produced: u8) -> Self;
+}
+
+#[cfg(test)]
+impl TestCard for ponder_db::card::Card {
+    fn with_type_line(mut self, type_line: &str) -> Self {
+        self.type_line = Some(type_line.to_string());
+        self
+    }
+
+    fn with_text(mut self, text: &str) -> Self {
+        self.oracle_text = Some(text.to_string());
+        self
+    }
+
+    fn with_identity(mut self, identity: u8) -> Self {
+        self.color_identity = Some(identity);
+        self
+    }
+
+    fn with_mana_value(mut self, mana_value: f32) -> Self {
+        self.converted_mana_cost = mana_value;
+        self
+    }
+
+    fn with_mana_cost(mut self, mana_cost: &str) -> Self {
+        self.mana_cost = Some(mana_cost.to_string());
+        self
+    }
+
+    fn with_produced_mana(mut self, produced: u8) -> Self {
+        self.produced_mana = Some(produced);
+        self
+    }
+}
+
+/// A deck named `Test` holding each card with its number of copies and zone
+#[cfg(test)]
+pub(crate) fn test_deck(
+    format: ponder_db::scryfall::Format,
+    cards: impl IntoIterator<Item = (ponder_db::card::Card, u32, ponder_db::deck::Zone)>,
+) -> Deck {
+    let mut deck = Deck::new("Test", format);
+    for (card, quantity, zone) in cards {
+        deck.add(card, quantity, zone, ponder_db::scryfall::Finish::Nonfoil);
+    }
+
+    deck
+}
@@ -0,0 +1,341 @@
+use ponder_db::{card::Card, deck::Zone};
+use rand::{SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
+
+use super::{Deck, probability::HAND_SIZE};
+
+/// Where a card is during a playtest
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameZone {
+    Library,
+    Hand,
+    Battlefield,
+    Graveyard,
+    Exile,
+    /// Commanders and signature spells
+    Command,
+}
+
+impl GameZone {
+    /// Zones shown on the playtest screen, the library stays face down
+    pub const VISIBLE: [GameZone; 5] = [
+        GameZone::Hand,
+        GameZone::Battlefield,
+        GameZone::Graveyard,
+        GameZone::Exile,
+        GameZone::Command,
+    ];
+}
+
+#[derive(Debug, Clone)]
+pub struct GameCard {
+    pub card: Card,
+    pub tapped: bool,
+}
+
+/// A goldfish game of a deck, shuffled from a seed so a session can be played again.
+///
+/// The shuffle uses ChaCha8 rather than `StdRng`, whose algorithm can change between rand
+/// releases, so a seed deals the same cards in every build.
+#[derive(Debug, Clone)]
+pub struct Playtest {
+    seed: u64,
+    rng: ChaCha8Rng,
+    deck: Vec<Card>,
+    command_cards: Vec<Card>,
+    /// Top of the library is the end of the list
+    pub library: Vec<GameCard>,
+    pub hand: Vec<GameCard>,
+    pub battlefield: Vec<GameCard>,
+    pub graveyard: Vec<GameCard>,
+    pub exile: Vec<GameCard>,
+    pub command: Vec<GameCard>,
+    /// Zero until the opening hand is kept
+    pub turn: u32,
+    pub on_the_draw: bool,
+    pub mulligans: u32,
+    /// Cards still to put on the bottom for the London mulligan
+    pub to_bottom: u32,
+}
+
+fn game_cards(cards: &[Card]) -> Vec<GameCard> {
+    cards
+        .iter()
+        .map(|card| GameCard {
+            card: card.clone(),
+            tapped: false,
+        })
+        .collect()
+}
+
+impl Playtest {
+    /// Shuffle the main deck and draw an opening hand
+    pub fn new(deck: &Deck, seed: u64, on_the_draw: bool) -> Self {
+        let copies = |zone: Zone| {
+            deck.entries(zone)
+                .flat_map(|entry| std::iter::repeat_n(entry.card.clone(), entry.quantity as usize))
+                .collect::<Vec<_>>()
+        };
+
+        let mut command_cards = copies(Zone::Commander);
+        command_cards.extend(copies(Zone::SignatureSpell));
+
+        let mut playtest = Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            deck: copies(Zone::Main),
+            command_cards,
+            library: Vec::new(),
+            hand: Vec::new(),
+            battlefield: Vec::new(),
+            graveyard: Vec::new(),
+            exile: Vec::new(),
+            command: Vec::new(),
+            turn: 0,
+            on_the_draw,
+            mulligans: 0,
+            to_bottom: 0,
+        };
+        playtest.restart();
+        playtest
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Start over with another shuffle
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.restart();
+    }
+
+    /// Start over from the same seed, dealing the same cards as before
+    pub fn restart(&mut self) {
+        self.rng = ChaCha8Rng::seed_from_u64(self.seed);
+        self.library = game_cards(&self.deck);
+        self.command = game_cards(&self.command_cards);
+        self.hand.clear();
+        self.battlefield.clear();
+        self.graveyard.clear();
+        self.exile.clear();
+        self.turn = 0;
+        self.mulligans = 0;
+        self.to_bottom = 0;
+        self.deal();
+    }
+
+    fn deal(&mut self) {
+        self.library.append(&mut self.hand);
+        self.library.shuffle(&mut self.rng);
+        for _ in 0..HAND_SIZE {
+            self.draw_card();
+        }
+    }
+
+    /// Shuffle the hand away and draw seven, to put one more on the bottom than last time
+    pub fn mulligan(&mut self) -> bool {
+        if self.turn > 0 || self.mulligans >= HAND_SIZE {
+            return false;
+        }
+
+        self.mulligans += 1;
+        self.to_bottom = self.mulligans;
+        self.deal();
+        true
+    }
+
+    /// Put a card from the hand on the bottom of the library after a mulligan
+    pub fn bottom(&mut self, index: usize) -> bool {
+        if self.to_bottom == 0 || index >= self.hand.len() {
+            return false;
+        }
+
+        let card = self.hand.remove(index);
+        self.library.insert(0, card);
+        self.to_bottom -= 1;
+        true
+    }
+
+    /// Keep the hand and start the first turn, once the mulligan cards are on the bottom
+    pub fn keep(&mut self) -> bool {
+        if self.turn > 0 || self.to_bottom > 0 {
+            return false;
+        }
+
+        self.turn = 1;
+        if self.on_the_draw {
+            self.draw_card();
+        }
+        true
+    }
+
+    /// Draw a card, once the opening hand is kept
+    pub fn draw(&mut self) -> bool {
+        self.turn > 0 && self.draw_card()
+    }
+
+    fn draw_card(&mut self) -> bool {
+        match self.library.pop() {
+            Some(card) => {
+                self.hand.push(card);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Untap everything and draw for the next turn, keeping the hand if it's the first
+    pub fn next_turn(&mut self) {
+        if self.turn == 0 {
+            self.keep();
+            return;
+        }
+
+        self.turn += 1;
+        for card in self.battlefield.iter_mut() {
+            card.tapped = false;
+        }
+        self.draw();
+    }
+
+    pub fn cards(&self, zone: GameZone) -> &[GameCard] {
+        match zone {
+            GameZone::Library => &self.library,
+            GameZone::Hand => &self.hand,
+            GameZone::Battlefield => &self.battlefield,
+            GameZone::Graveyard => &self.graveyard,
+            GameZone::Exile => &self.exile,
+            GameZone::Command => &self.command,
+        }
+    }
+
+    fn cards_mut(&mut self, zone: GameZone) -> &mut Vec<GameCard> {
+        match zone {
+            GameZone::Library => &mut self.library,
+            GameZone::Hand => &mut self.hand,
+            GameZone::Battlefield => &mut self.battlefield,
+            GameZone::Graveyard => &mut self.graveyard,
+            GameZone::Exile => &mut self.exile,
+            GameZone::Command => &mut self.command,
+        }
+    }
+
+    /// Move a card between zones, untapping it. Cards moved to the library go on top.
+    ///
+    /// Cards stay put until the opening hand is kept, so a mulligan shuffles back all of them.
+    pub fn move_card(&mut self, from: GameZone, index: usize, to: GameZone) -> bool {
+        if self.turn == 0 || from == to || index >= self.cards(from).len() {
+            return false;
+        }
+
+        let mut card = self.cards_mut(from).remove(index);
+        card.tapped = false;
+        self.cards_mut(to).push(card);
+        true
+    }
+
+    pub fn toggle_tap(&mut self, index: usize) -> bool {
+        match self.battlefield.get_mut(index) {
+            Some(card) => {
+                card.tapped = !card.tapped;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod playtest_tests {
+    use super::*;
+    use crate::data::test_card;
+    use ponder_db::scryfall::{Finish, Format};
+
+    fn deck() -> Deck {
+        let mut deck = Deck::new("Goldfish", Format::Modern);
+        for id in 0..20 {
+            deck.add(
+                test_card(id, &format!("Card {id}")),
+                3,
+                Zone::Main,
+                Finish::Nonfoil,
+            );
+        }
+        deck.add(test_card(99, "Duress"), 2, Zone::Sideboard, Finish::Nonfoil);
+        deck
+    }
+
+    fn names(cards: &[GameCard]) -> Vec<&str> {
+        cards.iter().map(|card| card.card.name.as_str()).collect()
+    }
+
+    #[test]
+    fn seeded_shuffle() {
+        let deck = deck();
+        let first = Playtest::new(&deck, 42, false);
+        let second = Playtest::new(&deck, 42, false);
+        assert_eq!(first.hand.len(), 7);
+        assert_eq!(first.library.len(), 53);
+        assert_eq!(names(&first.hand), names(&second.hand));
+        assert_eq!(names(&first.library), names(&second.library));
+
+        let mut replay = first.clone();
+        replay.mulligan();
+        replay.restart();
+        assert_eq!(names(&replay.hand), names(&first.hand));
+        assert_ne!(
+            names(&Playtest::new(&deck, 7, false).library),
+            names(&first.library)
+        );
+    }
+
+    #[test]
+    fn london_mulligan() {
+        let mut game = Playtest::new(&deck(), 1, true);
+        assert!(game.mulligan());
+        assert!(game.mulligan());
+        assert_eq!(game.hand.len(), 7);
+        assert_eq!(game.to_bottom, 2);
+        assert!(!game.keep());
+
+        let bottomed = game.hand[3].card.name.clone();
+        assert!(game.bottom(3));
+        assert!(game.bottom(0));
+        assert!(!game.bottom(0));
+        assert_eq!(game.hand.len(), 5);
+        assert_eq!(game.library[1].card.name, bottomed);
+
+        // Nothing leaves the opening hand before it's kept
+        assert!(!game.draw());
+        assert!(!game.move_card(GameZone::Hand, 0, GameZone::Battlefield));
+        assert_eq!((game.hand.len(), game.library.len()), (5, 55));
+
+        // On the draw the first turn draws a card
+        assert!(game.keep());
+        assert_eq!(game.turn, 1);
+        assert_eq!(game.hand.len(), 6);
+        assert!(!game.mulligan());
+    }
+
+    #[test]
+    fn turns_and_zones() {
+        let mut game = Playtest::new(&deck(), 3, false);
+        game.next_turn();
+        assert_eq!((game.turn, game.hand.len()), (1, 7));
+
+        assert!(game.move_card(GameZone::Hand, 0, GameZone::Battlefield));
+        assert!(game.toggle_tap(0));
+        assert!(game.battlefield[0].tapped);
+        game.next_turn();
+        assert_eq!(game.turn, 2);
+        assert!(!game.battlefield[0].tapped);
+        assert_eq!(game.hand.len(), 7);
+
+        assert!(game.move_card(GameZone::Battlefield, 0, GameZone::Graveyard));
+        assert!(game.move_card(GameZone::Graveyard, 0, GameZone::Exile));
+        assert!(!game.move_card(GameZone::Graveyard, 0, GameZone::Exile));
+        assert_eq!(game.exile.len(), 1);
+        assert_eq!(game.library.len(), 52);
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use ponder_db::{
+    SqliteStore,
+    deck::Zone,
+    price::{PricedCard, Prices},
+};
+
+use super::Deck;
+
+/// Cards listed as the most expensive
+pub const MOST_EXPENSIVE: usize = 10;
+
+/// Similar cards looked at for each expensive card
+const BUDGET_CANDIDATES: usize = 25;
+
+/// Cheaper cards suggested for each expensive card
+const BUDGET_ALTERNATIVES: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CardCost {
+    pub name: String,
+    pub zone: Zone,
+    pub quantity: u32,
+    /// Dollar price of the printing and finish in the deck
+    pub price: Option<f64>,
+    /// Set code and price of the cheapest printing legal in the deck's format
+    pub cheapest: Option<(String, f64)>,
+}
+
+impl CardCost {
+    pub fn total(&self) -> Option<f64> {
+        self.price.map(|price| price * self.quantity as f64)
+    }
+
+    /// Cost buying the cheapest printing, or the deck's printing when there's no other price
+    pub fn cheapest_total(&self) -> Option<f64> {
+        self.cheapest
+            .as_ref()
+            .map(|(_, price)| *price)
+            .or(self.price)
+            .map(|price| price * self.quantity as f64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZoneCost {
+    pub zone: Zone,
+    pub total: f64,
+    pub cheapest: f64,
+}
+
+/// What a deck costs in dollars, as printed and buying the cheapest printings
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CostReport {
+    pub cards: Vec<CardCost>,
+    pub zones: Vec<ZoneCost>,
+    /// Every zone but the maybeboard
+    pub total: f64,
+    pub cheapest: f64,
+}
+
+impl CostReport {
+    /// Work out the costs from printing prices keyed by Scryfall id and the cheapest legal
+    /// printings keyed by name
+    pub fn new(
+        deck: &Deck,
+        prices: &HashMap<String, Prices>,
+        cheapest: &HashMap<String, PricedCard>,
+    ) -> Self {
+        let mut report = Self::default();
+        for entry in &deck.cards {
+            report.cards.push(CardCost {
+                name: entry.card.name.clone(),
+                zone: entry.zone,
+                quantity: entry.quantity,
+                price: prices
+                    .get(&entry.card.card_id)
+                    .and_then(|prices| prices.usd_for(&entry.finish)),
+                cheapest: cheapest.get(&entry.card.name).and_then(|printing| {
+                    let set = printing.card.set_short.clone().unwrap_or_default();
+                    printing.prices.usd_any().map(|price| (set, price))
+                }),
+            });
+        }
+
+        for zone in Zone::ALL {
+            let cards = report.cards.iter().filter(|card| card.zone == zone);
+            let (total, cheapest) = cards.fold((0.0, 0.0), |(total, cheapest), card| {
+                (
+                    total + card.total().unwrap_or_default(),
+                    cheapest + card.cheapest_total().unwrap_or_default(),
+                )
+            });
+            if total > 0.0 || cheapest > 0.0 {
+                report.zones.push(ZoneCost {
+                    zone,
+                    total,
+                    cheapest,
+                });
+            }
+        }
+
+        for zone in report.zones.iter().filter(|zone| zone.zone.is_played()) {
+            report.total += zone.total;
+            report.cheapest += zone.cheapest;
+        }
+
+        report
+    }
+
+    /// Cards costing the most across all their copies, most expensive first
+    pub fn most_expensive(&self, limit: usize) -> Vec<&CardCost> {
+        let mut cards = self
+            .cards
+            .iter()
+            .filter(|card| card.total().is_some())
+            .collect::<Vec<_>>();
+        cards.sort_by(|a, b| {
+            b.total()
+                .unwrap_or_default()
+                .total_cmp(&a.total().unwrap_or_default())
+        });
+        cards.truncate(limit);
+        cards
+    }
+
+    /// Cards with no price at all
+    pub fn unpriced(&self) -> impl Iterator<Item = &CardCost> {
+        self.cards
+            .iter()
+            .filter(|card| card.cheapest_total().is_none())
+    }
+}
+
+/// Cheaper cards to play instead of an expensive one
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetSwap {
+    pub card: String,
+    pub price: f64,
+    /// Names and cheapest prices, closest match first
+    pub alternatives: Vec<(String, f64)>,
+}
+
+impl Deck {
+    pub async fn cost(&self, store: &SqliteStore) -> Result<CostReport> {
+        let ids = self
+            .cards
+            .iter()
+            .map(|entry| entry.card.card_id.as_str())
+            .collect::<Vec<_>>();
+        let names = self
+            .cards
+            .iter()
+            .map(|entry| entry.card.name.as_str())
+            .collect::<Vec<_>>();
+
+        let prices = store.prices(&ids).await?;
+        let cheapest = store.cheapest_printings(&names, &self.format).await?;
+        Ok(CostReport::new(self, &prices, &cheapest))
+    }
+
+    /// Suggest cheaper cards like each card whose cheapest printing costs more than `threshold`
+    /// dollars, legal in the deck's format and within its color identity
+    pub async fn budget(&self, store: &SqliteStore, threshold: f64) -> Result<Vec<BudgetSwap>> {
+        let report = self.cost(store).await?;
+        let in_deck = self
+            .cards
+            .iter()
+            .map(|entry| entry.card.name.as_str())
+            .collect::<HashSet<_>>();
+
+        let mut seen = HashSet::new();
+        let mut expensive = Vec::new();
+        for (entry, cost) in self.cards.iter().zip(&report.cards) {
+            let price = cost
+                .cheapest
+                .as_ref()
+                .map(|(_, price)| *price)
+                .or(cost.price);
+            let Some(price) = price.filter(|price| *price > threshold) else {
+                continue;
+            };
+            if !entry.zone.is_played() || !seen.insert(entry.card.name.as_str()) {
+                continue;
+            }
+
+            expensive.push((&entry.card, price));
+        }
+
+        let cards = expensive.iter().map(|(card, _)| *card).collect::<Vec<_>>();
+        let similar = store
+            .similar_cards_many(
+                &cards,
+                &self.format,
+                self.color_identity(),
+                BUDGET_CANDIDATES,
+            )
+            .await?;
+        let names = similar
+            .iter()
+            .map(|similar| {
+                similar
+                    .iter()
+                    .map(|card| card.name.as_str())
+                    .filter(|name| !in_deck.contains(name))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut all_names = names.concat();
+        all_names.sort();
+        all_names.dedup();
+        let prices = store.cheapest_printings(&all_names, &self.format).await?;
+
+        let swaps = expensive
+            .into_iter()
+            .zip(names)
+            .map(|((card, price), names)| BudgetSwap {
+                card: card.name.clone(),
+                price,
+                alternatives: names
+                    .into_iter()
+                    .filter_map(|name| {
+                        let price = prices.get(name)?.prices.usd_any()?;
+                        (price <= threshold).then(|| (name.to_string(), price))
+                    })
+                    .take(BUDGET_ALTERNATIVES)
+                    .collect(),
+            })
+            .collect();
+
+        Ok(swaps)
+    }
+}
+
+#[cfg(test)]
+mod price_tests {
+    use super::*;
+    use crate::data::test_card;
+    use ponder_db::scryfall::{Finish, Format};
+
+    #[test]
+    fn cost_report() {
+        let mut deck = Deck::new("Burn", Format::Modern);
+        deck.add(
+            test_card(1, "Lightning Bolt"),
+            4,
+            Zone::Main,
+            Finish::Nonfoil,
+        );
+        deck.add(test_card(2, "Goblin Guide"), 4, Zone::Main, Finish::Nonfoil);
+        deck.add(
+            test_card(3, "Smash to Smithereens"),
+            2,
+            Zone::Sideboard,
+            Finish::Nonfoil,
+        );
+        deck.add(
+            test_card(4, "Eidolon of the Great Revel"),
+            4,
+            Zone::Maybeboard,
+            Finish::Nonfoil,
+        );
+        deck.add(test_card(5, "Proxy"), 1, Zone::Main, Finish::Nonfoil);
+
+        let usd = |usd: f64| Prices {
+            usd: Some(usd),
+            ..Default::default()
+        };
+        let prices = HashMap::from([
+            ("card-1".to_string(), usd(3.0)),
+            ("card-2".to_string(), usd(1.5)),
+            ("card-3".to_string(), usd(0.25)),
+            ("card-4".to_string(), usd(10.0)),
+        ]);
+        let mut printing = test_card(10, "Lightning Bolt");
+        printing.set_short = Some("2xm".to_string());
+        let cheapest = HashMap::from([(
+            "Lightning Bolt".to_string(),
+            PricedCard {
+                card: printing,
+                prices: usd(1.0),
+            },
+        )]);
+
+        let report = CostReport::new(&deck, &prices, &cheapest);
+        assert_eq!(report.total, 18.5);
+        assert_eq!(report.cheapest, 10.5);
+        assert_eq!(
+            report.zones,
+            vec![
+                ZoneCost {
+                    zone: Zone::Main,
+                    total: 18.0,
+                    cheapest: 10.0
+                },
+                ZoneCost {
+                    zone: Zone::Sideboard,
+                    total: 0.5,
+                    cheapest: 0.5
+                },
+                ZoneCost {
+                    zone: Zone::Maybeboard,
+                    total: 40.0,
+                    cheapest: 40.0
+                },
+            ]
+        );
+
+        let names = report
+            .most_expensive(2)
+            .iter()
+            .map(|card| card.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Eidolon of the Great Revel", "Lightning Bolt"]);
+        assert_eq!(report.cards[0].cheapest, Some(("2xm".to_string(), 1.0)));
+        assert_eq!(report.unpriced().count(), 1);
+    }
+
+    #[test]
+    fn priced_by_finish() {
+        let mut deck = Deck::new("Artifacts", Format::Commander);
+        deck.add(test_card(1, "Sol Ring"), 1, Zone::Main, Finish::Nonfoil);
+        deck.add(test_card(1, "Sol Ring"), 1, Zone::Sideboard, Finish::Foil);
+        deck.add(test_card(2, "Arcane Signet"), 1, Zone::Main, Finish::Etched);
+
+        let prices = HashMap::from([
+            (
+                "card-1".to_string(),
+                Prices {
+                    usd: Some(1.0),
+                    usd_foil: Some(5.0),
+                    ..Default::default()
+                },
+            ),
+            (
+                "card-2".to_string(),
+                Prices {
+                    usd: Some(0.5),
+                    ..Default::default()
+                },
+            ),
+        ]);
+
+        // Without an etched price Arcane Signet falls back to its nonfoil one
+        let report = CostReport::new(&deck, &prices, &HashMap::new());
+        let prices = report
+            .cards
+            .iter()
+            .map(|card| card.price)
+            .collect::<Vec<_>>();
+        assert_eq!(prices, vec![Some(1.0), Some(5.0), Some(0.5)]);
+    }
+}
@@ -0,0 +1,288 @@
+use anyhow::{Context, Result, bail};
+use ponder_db::deck::Zone;
+
+use super::{CardExt, Deck, DeckEntry};
+
+/// Cards in an opening hand
+pub const HAND_SIZE: u32 = 7;
+
+/// Which cards count as a hit when drawing
+#[derive(Debug, Clone, PartialEq)]
+pub enum Category {
+    Tag(String),
+    /// Card type or supertype, e.g. `Land` or `Legendary`
+    Type(String),
+    /// Mana value within an inclusive range
+    ManaValue {
+        min: u32,
+        max: u32,
+    },
+    /// Cards by name
+    Cards(Vec<String>),
+}
+
+impl Category {
+    pub fn matches(&self, entry: &DeckEntry) -> bool {
+        match self {
+            Self::Tag(tag) => entry.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            Self::Type(card_type) => entry
+                .card
+                .types()
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(card_type)),
+            Self::ManaValue { min, max } => {
+                let mana_value = entry.card.converted_mana_cost as u32;
+                !entry.card.is_land() && (*min..=*max).contains(&mana_value)
+            }
+            Self::Cards(names) => names
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&entry.card.name)),
+        }
+    }
+}
+
+impl std::fmt::Display for Category {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tag(tag) => write!(f, "tag:{tag}"),
+            Self::Type(card_type) => write!(f, "type:{card_type}"),
+            Self::ManaValue { min, max } if min == max => write!(f, "mv:{min}"),
+            Self::ManaValue { min, max } => write!(f, "mv:{min}-{max}"),
+            Self::Cards(names) => write!(f, "cards:{}", names.join(",")),
+        }
+    }
+}
+
+/// Parse `tag:ramp`, `type:Creature`, `mv:2`, `mv:1-3` or `cards:Lightning Bolt,Chain Lightning`
+impl std::str::FromStr for Category {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, value) = s
+            .split_once(':')
+            .with_context(|| format!("expected <kind>:<value> - {s}"))?;
+        let value = value.trim();
+        if value.is_empty() {
+            bail!("missing value - {s}");
+        }
+
+        let category = match kind.trim().to_lowercase().as_str() {
+            "tag" => Self::Tag(value.to_string()),
+            "type" => Self::Type(value.to_string()),
+            "mv" | "cmc" => {
+                let (min, max) = value.split_once('-').unwrap_or((value, value));
+                Self::ManaValue {
+                    min: min.trim().parse().context("parsing mana value")?,
+                    max: max.trim().parse().context("parsing mana value")?,
+                }
+            }
+            "cards" | "card" => Self::Cards(
+                value
+                    .split(',')
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty())
+                    .collect(),
+            ),
+            _ => bail!("unknown category {kind}, expected tag, type, mv or cards"),
+        };
+
+        Ok(category)
+    }
+}
+
+/// Chance of drawing some number of a category's cards by a turn
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawQuery {
+    pub category: Category,
+    pub at_least: u32,
+    pub turn: u32,
+    pub on_the_draw: bool,
+    /// London mulligans taken, each putting a card from the opening seven on the bottom
+    pub mulligans: u32,
+}
+
+fn choose(n: u32, k: u32) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+
+    let k = k.min(n - k);
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// Chance of exactly `hits` successes in `draws` cards from a population
+pub fn hypergeometric(population: u32, successes: u32, draws: u32, hits: u32) -> f64 {
+    if draws > population || hits > draws || hits > successes {
+        return 0.0;
+    }
+
+    choose(successes, hits) * choose(population - successes, draws - hits)
+        / choose(population, draws)
+}
+
+/// Chance of at least `hits` successes in `draws` cards from a population
+pub fn at_least(population: u32, successes: u32, draws: u32, hits: u32) -> f64 {
+    let draws = draws.min(population);
+    if hits == 0 {
+        return 1.0;
+    } else if hits > successes.min(draws) {
+        return 0.0;
+    }
+
+    let below = (0..hits)
+        .map(|h| hypergeometric(population, successes, draws, h))
+        .sum::<f64>();
+    (1.0 - below).clamp(0.0, 1.0)
+}
+
+impl DrawQuery {
+    /// Cards drawn after the opening hand by the query's turn
+    pub fn draws(&self) -> u32 {
+        self.turn.saturating_sub(1) + u32::from(self.on_the_draw)
+    }
+
+    /// Chance of the query's cards being in hand by its turn, drawing from the main deck.
+    ///
+    /// After a mulligan the cards put on the bottom are the ones not in the category, so hits in
+    /// the opening seven are only lost when there are more of them than cards kept.
+    pub fn probability(&self, deck: &Deck) -> f64 {
+        let library = deck.count(Zone::Main) as u32;
+        let successes = deck
+            .entries(Zone::Main)
+            .filter(|entry| self.category.matches(entry))
+            .map(|entry| entry.quantity)
+            .sum::<u32>();
+
+        let kept = HAND_SIZE.saturating_sub(self.mulligans);
+        let opening = HAND_SIZE.min(library);
+        let draws = self.draws().min(library - opening);
+
+        (0..=opening.min(successes))
+            .map(|in_hand| {
+                let hand = hypergeometric(library, successes, opening, in_hand);
+                let needed = self.at_least.saturating_sub(in_hand.min(kept));
+                hand * at_least(library - opening, successes - in_hand, draws, needed)
+            })
+            .sum::<f64>()
+            .clamp(0.0, 1.0)
+    }
+}
+
+/// Chance of drawing the cards by each turn from the first up to `turns`
+pub fn odds_by_turn(
+    deck: &Deck,
+    category: &Category,
+    at_least: u32,
+    on_the_draw: bool,
+    mulligans: u32,
+    turns: u32,
+) -> Vec<f64> {
+    (1..=turns)
+        .map(|turn| {
+            DrawQuery {
+                category: category.clone(),
+                at_least,
+                turn,
+                on_the_draw,
+                mulligans,
+            }
+            .probability(deck)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod probability_tests {
+    use super::*;
+    use crate::data::test_card;
+    use ponder_db::scryfall::{Finish, Format};
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn deck() -> Deck {
+        let mut deck = Deck::new("Odds", Format::Modern);
+        for (id, name, type_line, mana_value, quantity) in [
+            (1, "Mountain", "Basic Land — Mountain", 0.0, 24),
+            (2, "Goblin Guide", "Creature — Goblin", 1.0, 8),
+            (3, "Ash Zealot", "Creature — Human Warrior", 2.0, 8),
+            (4, "Lightning Bolt", "Instant", 1.0, 20),
+        ] {
+            let mut card = test_card(id, name);
+            card.type_line = Some(type_line.to_string());
+            card.converted_mana_cost = mana_value;
+            deck.add(card, quantity, Zone::Main, Finish::Nonfoil);
+        }
+        deck.cards[3].tags = vec!["burn".to_string()];
+
+        deck
+    }
+
+    #[test]
+    fn hypergeometric_odds() {
+        assert!(close(hypergeometric(60, 4, 7, 0), 0.6005));
+        assert!(close(at_least(60, 4, 7, 1), 0.3995));
+        assert!(close(at_least(60, 24, 7, 2), 0.8573));
+        assert_eq!(at_least(60, 4, 7, 0), 1.0);
+        assert_eq!(at_least(60, 4, 7, 5), 0.0);
+    }
+
+    #[test]
+    fn categories() -> Result<()> {
+        let deck = deck();
+        let count = |category: &str| -> Result<usize> {
+            let category: Category = category.parse()?;
+            Ok(deck
+                .cards
+                .iter()
+                .filter(|entry| category.matches(entry))
+                .map(|entry| entry.quantity as usize)
+                .sum())
+        };
+
+        assert_eq!(count("type:land")?, 24);
+        assert_eq!(count("mv:1")?, 28);
+        assert_eq!(count("mv:0-2")?, 36);
+        assert_eq!(count("tag:Burn")?, 20);
+        assert_eq!(count("cards:Goblin Guide, Ash Zealot")?, 16);
+        assert!("colour:red".parse::<Category>().is_err());
+        assert_eq!("mv:1-3".parse::<Category>()?.to_string(), "mv:1-3");
+
+        Ok(())
+    }
+
+    #[test]
+    fn draw_odds() {
+        let deck = deck();
+        let two_drop = DrawQuery {
+            category: Category::ManaValue { min: 2, max: 2 },
+            at_least: 1,
+            turn: 2,
+            on_the_draw: false,
+            mulligans: 0,
+        };
+
+        // 8 hits in 60 cards, seeing 8 cards on the play and 9 on the draw
+        assert!(close(two_drop.probability(&deck), at_least(60, 8, 8, 1)));
+        let on_the_draw = DrawQuery {
+            on_the_draw: true,
+            ..two_drop.clone()
+        };
+        assert!(close(on_the_draw.probability(&deck), at_least(60, 8, 9, 1)));
+
+        // Keeping five cards on the play leaves no way to have six lands on turn one
+        let lands = DrawQuery {
+            category: Category::Type("Land".to_string()),
+            at_least: 6,
+            turn: 1,
+            on_the_draw: false,
+            mulligans: 2,
+        };
+        assert_eq!(lands.probability(&deck), 0.0);
+
+        let odds = odds_by_turn(&deck, &Category::Type("Land".to_string()), 3, false, 1, 4);
+        assert_eq!(odds.len(), 4);
+        assert!(odds.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
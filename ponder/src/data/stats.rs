@@ -0,0 +1,304 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use ponder_db::{SqliteStore, deck::Zone, scryfall::Color};
+
+use super::{CardExt, Deck, DeckEntry};
+
+/// Mana values at or above this share the last bucket of a curve
+pub const CURVE_MAX: usize = 7;
+
+/// Number of nonland cards at each mana value, `CURVE_MAX` and up in the last bucket
+pub type Curve<T> = [T; CURVE_MAX + 1];
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct CurveBucket {
+    pub permanents: usize,
+    pub spells: usize,
+}
+
+impl CurveBucket {
+    pub fn total(&self) -> usize {
+        self.permanents + self.spells
+    }
+}
+
+/// Pips of a color in mana costs against the lands that can make it
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ColorBalance {
+    pub color: Color,
+    pub pips: usize,
+    pub sources: usize,
+}
+
+/// Numbers for charting a deck, counting every copy of the cards in the deck's zones
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeckStats {
+    pub curve: Curve<CurveBucket>,
+    /// Curve of each zone with nonland cards in it, including the sideboard and maybeboard
+    pub zone_curves: Vec<(Zone, Curve<usize>)>,
+    /// Card types from most to least common
+    pub types: Vec<(String, usize)>,
+    /// Only colors the deck asks for or produces, in WUBRG order
+    pub colors: Vec<ColorBalance>,
+    pub average_mana_value: f32,
+    pub average_mana_value_nonland: f32,
+    /// Keywords from most to least common
+    pub keywords: Vec<(String, usize)>,
+    pub lands: usize,
+}
+
+fn curve_index(mana_value: f32) -> usize {
+    (mana_value.max(0.0) as usize).min(CURVE_MAX)
+}
+
+/// Colored pips in a mana cost, e.g. `{1}{W}{W/U}` has two white and one blue.
+///
+/// Hybrid symbols count towards each of their colors.
+pub fn pips(mana_cost: &str) -> BTreeMap<Color, usize> {
+    let mut pips = BTreeMap::new();
+    for symbol in mana_cost.split('{').filter_map(|s| s.strip_suffix('}')) {
+        for c in symbol.chars().filter(|c| "WUBRG".contains(*c)) {
+            *pips.entry(Color::from(c)).or_default() += 1;
+        }
+    }
+
+    pips
+}
+
+/// Sort counts from most to least common, then by name
+fn ranked(counts: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_by(|(a, x), (b, y)| y.cmp(x).then(a.cmp(b)));
+    counts
+}
+
+impl DeckStats {
+    /// Work out the stats from the card types and keywords of each card, keyed by `Card::id`
+    pub fn new(
+        deck: &Deck,
+        types: &HashMap<i32, Vec<String>>,
+        keywords: &HashMap<i32, Vec<String>>,
+    ) -> Self {
+        let mut stats = Self::default();
+        let deck_entries = deck
+            .cards
+            .iter()
+            .filter(|entry| entry.zone.is_deck())
+            .collect::<Vec<&DeckEntry>>();
+
+        let mut type_counts: HashMap<String, usize> = HashMap::new();
+        let mut keyword_counts: HashMap<String, usize> = HashMap::new();
+        let mut pip_counts: BTreeMap<Color, usize> = BTreeMap::new();
+        let mut source_counts: BTreeMap<Color, usize> = BTreeMap::new();
+        let mut total_mana_value = 0.0;
+        let mut nonland_mana_value = 0.0;
+
+        for entry in deck_entries.iter() {
+            let card = &entry.card;
+            let copies = entry.quantity as usize;
+            let mana_value = card.converted_mana_cost;
+            total_mana_value += mana_value * copies as f32;
+
+            for card_type in types.get(&card.id).into_iter().flatten() {
+                *type_counts.entry(card_type.clone()).or_default() += copies;
+            }
+            for keyword in keywords.get(&card.id).into_iter().flatten() {
+                *keyword_counts.entry(keyword.clone()).or_default() += copies;
+            }
+
+            if card.is_land() {
+                stats.lands += copies;
+                let produced = Color::from_bits(card.produced_mana.unwrap_or_default());
+                for color in produced {
+                    *source_counts.entry(color).or_default() += copies;
+                }
+                continue;
+            }
+
+            nonland_mana_value += mana_value * copies as f32;
+            let bucket = &mut stats.curve[curve_index(mana_value)];
+            if card.is_permanent() {
+                bucket.permanents += copies;
+            } else {
+                bucket.spells += copies;
+            }
+
+            for (color, count) in pips(card.mana_cost.as_deref().unwrap_or_default()) {
+                *pip_counts.entry(color).or_default() += count * copies;
+            }
+        }
+
+        let cards = deck_entries
+            .iter()
+            .map(|entry| entry.quantity as usize)
+            .sum::<usize>();
+        let nonland = cards - stats.lands;
+        if cards > 0 {
+            stats.average_mana_value = total_mana_value / cards as f32;
+        }
+        if nonland > 0 {
+            stats.average_mana_value_nonland = nonland_mana_value / nonland as f32;
+        }
+
+        for zone in Zone::ALL {
+            let mut curve = Curve::<usize>::default();
+            for entry in deck.entries(zone).filter(|entry| !entry.card.is_land()) {
+                curve[curve_index(entry.card.converted_mana_cost)] += entry.quantity as usize;
+            }
+
+            if curve.iter().any(|count| *count > 0) {
+                stats.zone_curves.push((zone, curve));
+            }
+        }
+
+        stats.colors = Color::WUBRG
+            .into_iter()
+            .map(|color| ColorBalance {
+                color,
+                pips: pip_counts.get(&color).copied().unwrap_or_default(),
+                sources: source_counts.get(&color).copied().unwrap_or_default(),
+            })
+            .filter(|balance| balance.pips > 0 || balance.sources > 0)
+            .collect();
+        stats.types = ranked(type_counts);
+        stats.keywords = ranked(keyword_counts);
+
+        stats
+    }
+}
+
+impl Deck {
+    pub async fn stats(&self, store: &SqliteStore) -> Result<DeckStats> {
+        let ids = self
+            .cards
+            .iter()
+            .map(|entry| entry.card.id)
+            .collect::<Vec<i32>>();
+        let types = store.card_types(&ids).await?;
+        let keywords = store.card_keywords(&ids).await?;
+
+        Ok(DeckStats::new(self, &types, &keywords))
+    }
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use super::*;
+    use crate::data::test_card;
+    use ponder_db::scryfall::{Finish, Format};
+
+    #[test]
+    fn mana_pips() {
+        let pips = pips("{2}{W}{W}{W/U}{B/P}{C}");
+        assert_eq!(pips.get(&Color::White), Some(&3));
+        assert_eq!(pips.get(&Color::Blue), Some(&1));
+        assert_eq!(pips.get(&Color::Black), Some(&1));
+        assert_eq!(pips.get(&Color::Green), None);
+    }
+
+    #[test]
+    fn deck_stats() {
+        let mut deck = Deck::new("Stats", Format::Modern);
+        let mut types = HashMap::new();
+        let mut keywords = HashMap::new();
+        for (id, name, type_line, cost, mana_value, quantity, zone) in [
+            (1, "Lightning Bolt", "Instant", "{R}", 1.0, 4, Zone::Main),
+            (
+                2,
+                "Goblin Guide",
+                "Creature — Goblin",
+                "{R}",
+                1.0,
+                4,
+                Zone::Main,
+            ),
+            (3, "Boros Charm", "Instant", "{R}{W}", 2.0, 4, Zone::Main),
+            (
+                4,
+                "Emrakul",
+                "Creature — Eldrazi",
+                "{15}",
+                15.0,
+                1,
+                Zone::Main,
+            ),
+            (
+                5,
+                "Sacred Foundry",
+                "Land — Mountain Plains",
+                "",
+                0.0,
+                4,
+                Zone::Main,
+            ),
+            (
+                6,
+                "Mountain",
+                "Basic Land — Mountain",
+                "",
+                0.0,
+                3,
+                Zone::Main,
+            ),
+            (7, "Duress", "Sorcery", "{B}", 1.0, 2, Zone::Sideboard),
+        ] {
+            let mut card = test_card(id, name);
+            card.type_line = Some(type_line.to_string());
+            card.mana_cost = Some(cost.to_string());
+            card.converted_mana_cost = mana_value;
+            card.produced_mana = match id {
+                5 => Some(Color::Red as u8 | Color::White as u8),
+                6 => Some(Color::Red as u8),
+                _ => None,
+            };
+            types.insert(
+                id,
+                card.types()
+                    .into_iter()
+                    .filter(|t| *t != "Basic")
+                    .map(str::to_string)
+                    .collect(),
+            );
+            deck.add(card, quantity, zone, Finish::Nonfoil);
+        }
+        keywords.insert(2, vec!["Haste".to_string()]);
+
+        let stats = DeckStats::new(&deck, &types, &keywords);
+        assert_eq!(stats.lands, 7);
+        assert_eq!(
+            stats.curve[1],
+            CurveBucket {
+                permanents: 4,
+                spells: 4
+            }
+        );
+        assert_eq!(stats.curve[2].spells, 4);
+        assert_eq!(stats.curve[CURVE_MAX].permanents, 1);
+        assert_eq!(stats.zone_curves.len(), 2);
+        assert_eq!(stats.zone_curves[1].0, Zone::Sideboard);
+        assert_eq!(stats.zone_curves[1].1[1], 2);
+
+        assert_eq!(stats.types[0], ("Instant".to_string(), 8));
+        assert_eq!(stats.types[1], ("Land".to_string(), 7));
+        assert_eq!(stats.keywords, vec![("Haste".to_string(), 4)]);
+        assert_eq!(
+            stats.colors,
+            vec![
+                ColorBalance {
+                    color: Color::White,
+                    pips: 4,
+                    sources: 4
+                },
+                ColorBalance {
+                    color: Color::Red,
+                    pips: 12,
+                    sources: 7
+                },
+            ]
+        );
+
+        assert!((stats.average_mana_value - 31.0 / 20.0).abs() < 1e-6);
+        assert!((stats.average_mana_value_nonland - 31.0 / 13.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,509 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::Result;
+use ponder_db::{
+    SqliteStore,
+    card::Card,
+    deck::Zone,
+    scryfall::{Format, Legality, Rarity},
+    similar::gameplay_key,
+};
+
+use super::{
+    CardExt, Deck,
+    commander::{is_commander_format, validate_commander},
+    companion::{is_companion, validate_companion},
+};
+
+/// Deck construction rules for a format
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FormatRules {
+    /// Main deck size, including any commanders
+    pub min_deck_size: usize,
+    pub max_deck_size: Option<usize>,
+    /// Copies allowed across the main deck and sideboard
+    pub max_copies: usize,
+    pub max_sideboard: usize,
+}
+
+impl FormatRules {
+    pub fn for_format(format: &Format) -> Self {
+        let constructed = Self {
+            min_deck_size: 60,
+            max_deck_size: None,
+            max_copies: 4,
+            max_sideboard: 15,
+        };
+
+        let singleton = |size: usize| Self {
+            min_deck_size: size,
+            max_deck_size: Some(size),
+            max_copies: 1,
+            max_sideboard: 0,
+        };
+
+        match format {
+            Format::Commander
+            | Format::Duel
+            | Format::Brawl
+            | Format::PauperCommander
+            | Format::Predh
+            | Format::Gladiator => singleton(100),
+            Format::Oathbreaker | Format::StandardBrawl => singleton(60),
+            Format::Standard
+            | Format::Future
+            | Format::Historic
+            | Format::Timeless
+            | Format::Pioneer
+            | Format::Explorer
+            | Format::Modern
+            | Format::Legacy
+            | Format::Pauper
+            | Format::Vintage
+            | Format::Penny
+            | Format::Alchemy
+            | Format::Oldschool
+            | Format::Premodern => constructed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    DeckTooSmall {
+        count: usize,
+        minimum: usize,
+    },
+    DeckTooLarge {
+        count: usize,
+        maximum: usize,
+    },
+    SideboardTooLarge {
+        count: usize,
+        maximum: usize,
+    },
+    TooManyCopies {
+        card: String,
+        count: usize,
+        maximum: usize,
+    },
+    Banned {
+        card: String,
+    },
+    Restricted {
+        card: String,
+        count: usize,
+    },
+    NotLegal {
+        card: String,
+    },
+    MissingCommander,
+    TooManyCommanders {
+        count: usize,
+    },
+    IneligibleCommander {
+        card: String,
+    },
+    InvalidPartners {
+        first: String,
+        second: String,
+    },
+    SignatureSpells {
+        count: usize,
+        expected: usize,
+    },
+    OutsideColorIdentity {
+        card: String,
+    },
+    /// `card` is `None` when the deck as a whole breaks the restriction
+    CompanionRestriction {
+        companion: String,
+        card: Option<String>,
+    },
+}
+
+impl Violation {
+    /// The card this violation is about, if it's not about the deck as a whole
+    pub fn card(&self) -> Option<&str> {
+        match self {
+            Self::TooManyCopies { card, .. }
+            | Self::Banned { card }
+            | Self::Restricted { card, .. }
+            | Self::NotLegal { card }
+            | Self::IneligibleCommander { card }
+            | Self::InvalidPartners { first: card, .. }
+            | Self::OutsideColorIdentity { card } => Some(card),
+            Self::CompanionRestriction { companion, card } => {
+                Some(card.as_deref().unwrap_or(companion))
+            }
+            Self::DeckTooSmall { .. }
+            | Self::DeckTooLarge { .. }
+            | Self::SideboardTooLarge { .. }
+            | Self::MissingCommander
+            | Self::TooManyCommanders { .. }
+            | Self::SignatureSpells { .. } => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DeckTooSmall { count, minimum } => {
+                write!(f, "deck has {count} cards, needs at least {minimum}")
+            }
+            Self::DeckTooLarge { count, maximum } => {
+                write!(f, "deck has {count} cards, maximum is {maximum}")
+            }
+            Self::SideboardTooLarge { count, maximum } => {
+                write!(f, "sideboard has {count} cards, maximum is {maximum}")
+            }
+            Self::TooManyCopies {
+                card,
+                count,
+                maximum,
+            } => write!(f, "{count} copies of {card}, maximum is {maximum}"),
+            Self::Banned { card } => write!(f, "{card} is banned"),
+            Self::Restricted { card, count } => {
+                write!(f, "{card} is restricted but has {count} copies")
+            }
+            Self::NotLegal { card } => write!(f, "{card} is not legal"),
+            Self::MissingCommander => write!(f, "deck has no commander"),
+            Self::TooManyCommanders { count } => {
+                write!(f, "deck has {count} commanders, maximum is 2")
+            }
+            Self::IneligibleCommander { card } => write!(f, "{card} can't be a commander"),
+            Self::InvalidPartners { first, second } => {
+                write!(f, "{first} and {second} can't be commanders together")
+            }
+            Self::SignatureSpells { count, expected } => {
+                write!(f, "deck has {count} signature spells, needs {expected}")
+            }
+            Self::OutsideColorIdentity { card } => {
+                write!(f, "{card} is outside the commander's color identity")
+            }
+            Self::CompanionRestriction {
+                companion,
+                card: Some(card),
+            } => write!(f, "{card} breaks {companion}'s companion restriction"),
+            Self::CompanionRestriction {
+                companion,
+                card: None,
+            } => write!(f, "deck breaks {companion}'s companion restriction"),
+        }
+    }
+}
+
+/// A card that plays the same as `duplicate` under another name, e.g. a Universes Beyond
+/// reprint. Legal, but worth pointing out in a singleton deck.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionalDuplicate {
+    pub card: String,
+    pub duplicate: String,
+}
+
+impl std::fmt::Display for FunctionalDuplicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} plays the same as {}", self.card, self.duplicate)
+    }
+}
+
+/// How many copies of a card the card itself allows, e.g. Relentless Rats or Seven Dwarves
+fn copy_exemption(card: &Card) -> Option<usize> {
+    if card.is_basic_land() {
+        return Some(usize::MAX);
+    }
+
+    let text = card.oracle_text.as_deref()?;
+    if text.contains("A deck can have any number of cards named") {
+        return Some(usize::MAX);
+    }
+
+    let (_, rest) = text.split_once("A deck can have up to ")?;
+    let count = match rest.split_whitespace().next()? {
+        "two" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        "six" => 6,
+        "seven" => 7,
+        "eight" => 8,
+        "nine" => 9,
+        "ten" => 10,
+        _ => return None,
+    };
+
+    Some(count)
+}
+
+/// Check a deck against its format's rules given each card's legality, keyed by `Card::id`
+pub fn validate(deck: &Deck, legalities: &HashMap<i32, Legality>) -> Vec<Violation> {
+    let rules = FormatRules::for_format(&deck.format);
+    let mut violations = Vec::new();
+
+    let deck_size = deck.deck_size();
+    if deck_size < rules.min_deck_size {
+        violations.push(Violation::DeckTooSmall {
+            count: deck_size,
+            minimum: rules.min_deck_size,
+        });
+    }
+
+    if let Some(maximum) = rules.max_deck_size
+        && deck_size > maximum
+    {
+        violations.push(Violation::DeckTooLarge {
+            count: deck_size,
+            maximum,
+        });
+    }
+
+    // Formats without a sideboard still keep their companion outside the game
+    let sideboard_size = deck
+        .entries(Zone::Sideboard)
+        .chain(deck.entries(Zone::Companion))
+        .filter(|entry| rules.max_sideboard > 0 || !is_companion(&entry.card))
+        .map(|entry| entry.quantity as usize)
+        .sum::<usize>();
+    if sideboard_size > rules.max_sideboard {
+        violations.push(Violation::SideboardTooLarge {
+            count: sideboard_size,
+            maximum: rules.max_sideboard,
+        });
+    }
+
+    // Copies are counted by name across every printing in the deck and sideboard. Cards in
+    // the other played zones still have to be legal.
+    let mut copies: BTreeMap<&str, (usize, &Card)> = BTreeMap::new();
+    for entry in deck.cards.iter().filter(|entry| entry.zone.is_played()) {
+        let (count, _) = copies
+            .entry(entry.card.name.as_str())
+            .or_insert((0, &entry.card));
+
+        if entry.zone.is_deck() || matches!(entry.zone, Zone::Sideboard | Zone::Companion) {
+            *count += entry.quantity as usize;
+        }
+    }
+
+    for (name, (count, card)) in copies {
+        let legality = deck
+            .cards
+            .iter()
+            .filter(|entry| entry.zone.is_played() && entry.card.name == name)
+            .find_map(|entry| legalities.get(&entry.card.id));
+
+        match legality {
+            Some(Legality::Banned) => violations.push(Violation::Banned {
+                card: name.to_string(),
+            }),
+            Some(Legality::NotLegal) => violations.push(Violation::NotLegal {
+                card: name.to_string(),
+            }),
+            Some(Legality::Restricted) if count > 1 => violations.push(Violation::Restricted {
+                card: name.to_string(),
+                count,
+            }),
+            _ => {}
+        }
+
+        let maximum = copy_exemption(card).unwrap_or(rules.max_copies);
+        if count > maximum {
+            violations.push(Violation::TooManyCopies {
+                card: name.to_string(),
+                count,
+                maximum,
+            });
+        }
+    }
+
+    violations
+}
+
+/// Cards in a singleton deck that play the same as another card in it
+pub fn functional_duplicates(deck: &Deck) -> Vec<FunctionalDuplicate> {
+    let mut duplicates = Vec::new();
+    if FormatRules::for_format(&deck.format).max_copies != 1 {
+        return duplicates;
+    }
+
+    let mut names: HashMap<String, &str> = HashMap::new();
+    for entry in deck.cards.iter().filter(|entry| entry.zone.is_deck()) {
+        let card = &entry.card;
+        if card.type_line.is_none() || copy_exemption(card).is_some() {
+            continue;
+        }
+
+        let first = names.entry(gameplay_key(card)).or_insert(&card.name);
+        if *first != card.name {
+            duplicates.push(FunctionalDuplicate {
+                card: card.name.clone(),
+                duplicate: first.to_string(),
+            });
+        }
+    }
+
+    duplicates
+}
+
+impl Deck {
+    pub async fn validate(&self, store: &SqliteStore) -> Result<Vec<Violation>> {
+        let ids = self
+            .cards
+            .iter()
+            .map(|entry| entry.card.id)
+            .collect::<Vec<i32>>();
+        let legalities = store.legalities(&ids, &self.format).await?;
+
+        let mut violations = validate(self, &legalities);
+        violations.extend(validate_companion(self));
+
+        if is_commander_format(&self.format) {
+            let mut uncommons = HashSet::new();
+            if self.format == Format::PauperCommander {
+                for entry in self.commanders() {
+                    let rarities = store.printed_rarities(&entry.card.name).await?;
+                    if rarities.contains(&Rarity::Uncommon) {
+                        uncommons.insert(entry.card.name.clone());
+                    }
+                }
+            }
+
+            violations.extend(validate_commander(self, &uncommons));
+        }
+
+        Ok(violations)
+    }
+}
+
+#[cfg(test)]
+mod validate_tests {
+    use super::*;
+    use crate::data::{test_card, test_deck};
+
+    #[test]
+    fn constructed_rules() {
+        let mut rats = test_card(2, "Relentless Rats");
+        rats.oracle_text =
+            Some("A deck can have any number of cards named Relentless Rats.".to_string());
+        let mut mountain = test_card(3, "Mountain");
+        mountain.type_line = Some("Basic Land — Mountain".to_string());
+
+        let deck = test_deck(
+            Format::Modern,
+            vec![
+                (test_card(1, "Lightning Bolt"), 4, Zone::Main),
+                (test_card(1, "Lightning Bolt"), 1, Zone::Sideboard),
+                (rats, 20, Zone::Main),
+                (mountain, 35, Zone::Main),
+                (test_card(4, "Ponder"), 1, Zone::Main),
+                (test_card(5, "Smash to Smithereens"), 15, Zone::Sideboard),
+                (test_card(1, "Lightning Bolt"), 4, Zone::Maybeboard),
+                (test_card(6, "Goblin Guide"), 8, Zone::Maybeboard),
+            ],
+        );
+
+        let legalities = HashMap::from([(4, Legality::Banned), (1, Legality::Legal)]);
+        let violations = validate(&deck, &legalities);
+        assert_eq!(
+            violations,
+            vec![
+                Violation::SideboardTooLarge {
+                    count: 16,
+                    maximum: 15
+                },
+                Violation::TooManyCopies {
+                    card: "Lightning Bolt".to_string(),
+                    count: 5,
+                    maximum: 4
+                },
+                Violation::Banned {
+                    card: "Ponder".to_string()
+                },
+                Violation::TooManyCopies {
+                    card: "Smash to Smithereens".to_string(),
+                    count: 15,
+                    maximum: 4
+                },
+            ]
+        );
+        assert_eq!(violations[1].card(), Some("Lightning Bolt"));
+    }
+
+    #[test]
+    fn singleton_rules() {
+        let mut dwarves = test_card(2, "Seven Dwarves");
+        dwarves.oracle_text =
+            Some("A deck can have up to seven cards named Seven Dwarves.".to_string());
+
+        let deck = test_deck(
+            Format::Commander,
+            vec![
+                (test_card(1, "Sol Ring"), 2, Zone::Main),
+                (dwarves, 7, Zone::Main),
+                (test_card(3, "Black Lotus"), 1, Zone::Commander),
+            ],
+        );
+
+        let legalities = HashMap::from([(3, Legality::Banned)]);
+        assert_eq!(
+            validate(&deck, &legalities),
+            vec![
+                Violation::DeckTooSmall {
+                    count: 10,
+                    minimum: 100
+                },
+                Violation::Banned {
+                    card: "Black Lotus".to_string()
+                },
+                Violation::TooManyCopies {
+                    card: "Sol Ring".to_string(),
+                    count: 2,
+                    maximum: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn functional_reprints() {
+        let mut cards = Vec::new();
+        for (id, name) in [(1, "Lightning Bolt"), (2, "Blaster Bolt"), (3, "Shock")] {
+            let mut card = test_card(id, name);
+            card.type_line = Some("Instant".to_string());
+            card.mana_cost = Some("{R}".to_string());
+            let damage = if name == "Shock" { 2 } else { 3 };
+            card.oracle_text = Some(format!("{name} deals {damage} damage to any target."));
+            cards.push((card, 1, Zone::Main));
+        }
+
+        let deck = test_deck(Format::Commander, cards.clone());
+        assert_eq!(
+            functional_duplicates(&deck),
+            vec![FunctionalDuplicate {
+                card: "Blaster Bolt".to_string(),
+                duplicate: "Lightning Bolt".to_string()
+            }]
+        );
+        // Legal in singleton formats, so only the deck size is a violation
+        assert_eq!(validate(&deck, &HashMap::new()).len(), 1);
+
+        let deck = test_deck(Format::Modern, cards);
+        assert!(functional_duplicates(&deck).is_empty());
+    }
+
+    #[test]
+    fn restricted_cards() {
+        let deck = test_deck(
+            Format::Vintage,
+            vec![(test_card(1, "Ancestral Recall"), 2, Zone::Main)],
+        );
+
+        let legalities = HashMap::from([(1, Legality::Restricted)]);
+        assert!(
+            validate(&deck, &legalities).contains(&Violation::Restricted {
+                card: "Ancestral Recall".to_string(),
+                count: 2
+            })
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Cockatrice `.cod` XML.
+//!
+//! Cockatrice only has main and side zones, so commanders and the other zones go in the side
+//! zone and the maybeboard is left out.
+
+use anyhow::{Context, Result};
+use ponder_db::{deck::Zone, scryfall::Finish};
+use quick_xml::se::Serializer;
+use serde::{Deserialize, Serialize};
+
+use super::{Decklist, DecklistLine};
+use crate::data::Deck;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename = "cockatrice_deck")]
+struct CodFile {
+    #[serde(rename = "@version", default)]
+    version: u32,
+    #[serde(default)]
+    deckname: String,
+    #[serde(default)]
+    comments: String,
+    #[serde(rename = "zone", default)]
+    zones: Vec<CodZone>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CodZone {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "card", default)]
+    cards: Vec<CodCard>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CodCard {
+    #[serde(rename = "@number")]
+    number: u32,
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(
+        rename = "@setShortName",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    set: Option<String>,
+    #[serde(
+        rename = "@collectorNumber",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    collector_number: Option<String>,
+}
+
+pub fn parse(input: &str) -> Result<Decklist> {
+    let cod: CodFile = quick_xml::de::from_str(input).context("parsing Cockatrice .cod")?;
+
+    let mut lines = Vec::new();
+    for cod_zone in cod.zones {
+        let zone = match cod_zone.name.as_str() {
+            "main" => Zone::Main,
+            "side" => Zone::Sideboard,
+            // Tokens and anything else aren't part of the deck
+            _ => continue,
+        };
+
+        for card in cod_zone.cards {
+            lines.push(DecklistLine {
+                line_number: lines.len() + 1,
+                quantity: card.number,
+                name: card.name,
+                set: card.set.filter(|set| !set.is_empty()),
+                collector_number: card.collector_number.filter(|number| !number.is_empty()),
+                mtgo_id: None,
+                scryfall_id: None,
+                finish: Finish::Nonfoil,
+                tags: Vec::new(),
+                zone,
+            });
+        }
+    }
+
+    let name = (!cod.deckname.is_empty()).then_some(cod.deckname);
+    Ok(Decklist { name, lines })
+}
+
+pub fn export(deck: &Deck) -> Result<String> {
+    let mut zones = Vec::new();
+    let side = Zone::ALL
+        .into_iter()
+        .filter(|zone| *zone != Zone::Main && zone.is_played())
+        .collect::<Vec<_>>();
+
+    for (name, zone_list) in [("main", vec![Zone::Main]), ("side", side)] {
+        let cards = zone_list
+            .iter()
+            .flat_map(|zone| deck.entries(*zone))
+            .map(|entry| CodCard {
+                number: entry.quantity,
+                name: entry.card.name.clone(),
+                set: entry.card.set_short.as_deref().map(str::to_uppercase),
+                collector_number: entry.card.collector_number.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        if !cards.is_empty() {
+            zones.push(CodZone {
+                name: name.to_string(),
+                cards,
+            });
+        }
+    }
+
+    let cod = CodFile {
+        version: 1,
+        deckname: deck.name.clone(),
+        comments: String::new(),
+        zones,
+    };
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let mut serializer = Serializer::new(&mut out);
+    serializer.indent(' ', 4);
+    cod.serialize(serializer)
+        .context("writing Cockatrice .cod")?;
+    out.push('\n');
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod cockatrice_tests {
+    use super::*;
+    use crate::decklist::{assert_round_trip, test_deck};
+
+    #[test]
+    fn export_cod() -> Result<()> {
+        let deck = test_deck();
+        let exported = export(&deck)?;
+        assert_eq!(exported, include_str!("../../testdata/burn.cod"));
+
+        let decklist = parse(&exported)?;
+        assert_eq!(decklist.name.as_deref(), Some("Boros Burn"));
+        assert_round_trip(&deck, &decklist, true);
+
+        Ok(())
+    }
+}
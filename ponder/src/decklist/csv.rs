@@ -0,0 +1,511 @@
+//! CSV exports from web deckbuilders and scanner apps.
+//!
+//! Each site names its columns differently, so a [`CsvMapping`] says which header holds which
+//! field. Mappings for Moxfield, Archidekt and ManaBox are built in and picked from the header
+//! row when importing.
+
+use anyhow::{Context, Result, bail};
+use ponder_db::{collection::CollectionEntry, deck::Zone, scryfall::Finish};
+use serde::{Deserialize, Serialize};
+
+use super::{Decklist, DecklistLine};
+use crate::data::Deck;
+
+/// Built in column mappings
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CsvSite {
+    Moxfield,
+    Archidekt,
+    ManaBox,
+}
+
+impl CsvSite {
+    pub const ALL: [CsvSite; 3] = [Self::Archidekt, Self::ManaBox, Self::Moxfield];
+
+    pub fn mapping(&self) -> CsvMapping {
+        let column = |name: &str| Some(name.to_string());
+        let finishes = |nonfoil: &str, foil: &str, etched: &str| {
+            [nonfoil.to_string(), foil.to_string(), etched.to_string()]
+        };
+
+        match self {
+            Self::Moxfield => CsvMapping {
+                quantity: "Count".to_string(),
+                name: "Name".to_string(),
+                set: column("Edition"),
+                collector_number: column("Collector Number"),
+                scryfall_id: None,
+                finish: column("Foil"),
+                condition: column("Condition"),
+                language: column("Language"),
+                tags: column("Tags"),
+                board: None,
+                location: None,
+                finish_names: finishes("", "foil", "etched"),
+            },
+            Self::Archidekt => CsvMapping {
+                quantity: "Quantity".to_string(),
+                name: "Name".to_string(),
+                set: column("Edition Code"),
+                collector_number: column("Collector Number"),
+                scryfall_id: column("Scryfall ID"),
+                finish: column("Finish"),
+                condition: column("Condition"),
+                language: column("Language"),
+                tags: column("Categories"),
+                board: column("Categories"),
+                location: None,
+                finish_names: finishes("Normal", "Foil", "Etched"),
+            },
+            Self::ManaBox => CsvMapping {
+                quantity: "Quantity".to_string(),
+                name: "Name".to_string(),
+                set: column("Set code"),
+                collector_number: column("Collector number"),
+                scryfall_id: column("Scryfall ID"),
+                finish: column("Foil"),
+                condition: column("Condition"),
+                language: column("Language"),
+                tags: None,
+                board: None,
+                location: column("Binder Name"),
+                finish_names: finishes("normal", "foil", "etched"),
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for CsvSite {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "moxfield" => Ok(Self::Moxfield),
+            "archidekt" => Ok(Self::Archidekt),
+            "manabox" => Ok(Self::ManaBox),
+            _ => bail!("unknown CSV site - {s}"),
+        }
+    }
+}
+
+/// Which column header holds each field. Optional fields are skipped when missing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CsvMapping {
+    pub quantity: String,
+    pub name: String,
+    pub set: Option<String>,
+    pub collector_number: Option<String>,
+    pub scryfall_id: Option<String>,
+    pub finish: Option<String>,
+    pub condition: Option<String>,
+    pub language: Option<String>,
+    /// Comma separated tags or categories
+    pub tags: Option<String>,
+    /// Zone of each card, e.g. `Commander` or `Sideboard`. Can share a column with the tags.
+    pub board: Option<String>,
+    /// Binder or box a collection keeps the card in
+    #[serde(default)]
+    pub location: Option<String>,
+    /// How the site writes nonfoil, foil and etched
+    pub finish_names: [String; 3],
+}
+
+/// A CSV row along with the fields decklists don't carry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvRecord {
+    pub line: DecklistLine,
+    pub condition: Option<String>,
+    pub language: Option<String>,
+    pub location: Option<String>,
+}
+
+fn parse_finish(value: &str) -> Finish {
+    match value.trim().to_lowercase().as_str() {
+        "foil" | "true" | "yes" => Finish::Foil,
+        "etched" => Finish::Etched,
+        _ => Finish::Nonfoil,
+    }
+}
+
+/// Zone named by a board value, `None` for values that are tags or categories
+fn parse_board(value: &str) -> Option<Zone> {
+    let value = value.trim().to_lowercase();
+    match value.as_str() {
+        "mainboard" | "deck" => Some(Zone::Main),
+        "considering" => Some(Zone::Maybeboard),
+        _ => Zone::ALL
+            .into_iter()
+            .find(|zone| zone.label().to_lowercase() == value),
+    }
+}
+
+impl CsvMapping {
+    fn columns(&self) -> Vec<&str> {
+        let mut columns = vec![self.quantity.as_str(), self.name.as_str()];
+        for column in [
+            &self.set,
+            &self.collector_number,
+            &self.scryfall_id,
+            &self.finish,
+            &self.condition,
+            &self.language,
+            &self.tags,
+            &self.board,
+            &self.location,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if !columns.contains(&column.as_str()) {
+                columns.push(column);
+            }
+        }
+
+        columns
+    }
+
+    fn finish_name(&self, finish: &Finish) -> String {
+        match finish {
+            Finish::Foil => self.finish_names[1].clone(),
+            Finish::Etched => self.finish_names[2].clone(),
+            _ => self.finish_names[0].clone(),
+        }
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Vec<CsvRecord>> {
+        let mut reader = ::csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(input.trim_start_matches('\u{feff}').as_bytes());
+        let headers = reader.headers().context("reading CSV headers")?.clone();
+        let index = |column: &Option<String>| {
+            column
+                .as_ref()
+                .and_then(|column| headers.iter().position(|h| h.eq_ignore_ascii_case(column)))
+        };
+
+        let quantity = index(&Some(self.quantity.clone()))
+            .with_context(|| format!("CSV has no {} column", self.quantity))?;
+        let name = index(&Some(self.name.clone()))
+            .with_context(|| format!("CSV has no {} column", self.name))?;
+        let set = index(&self.set);
+        let collector_number = index(&self.collector_number);
+        let scryfall_id = index(&self.scryfall_id);
+        let finish = index(&self.finish);
+        let condition = index(&self.condition);
+        let language = index(&self.language);
+        let tags = index(&self.tags);
+        let board = index(&self.board);
+        let location = index(&self.location);
+
+        let mut records = Vec::new();
+        for (i, row) in reader.records().enumerate() {
+            let row = row.with_context(|| format!("reading CSV row {}", i + 2))?;
+            let field = |index: Option<usize>| {
+                index
+                    .and_then(|index| row.get(index))
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(str::to_string)
+            };
+
+            let Some(card_name) = field(Some(name)) else {
+                continue;
+            };
+
+            let mut zone = Zone::Main;
+            if let Some(value) = field(board) {
+                zone = value
+                    .split(',')
+                    .rev()
+                    .find_map(parse_board)
+                    .unwrap_or(Zone::Main);
+            }
+
+            let tags = field(tags)
+                .map(|value| {
+                    value
+                        .split(',')
+                        .filter(|token| parse_board(token).is_none())
+                        .map(|token| token.trim().to_string())
+                        .filter(|token| !token.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            records.push(CsvRecord {
+                line: DecklistLine {
+                    line_number: i + 2,
+                    quantity: field(Some(quantity))
+                        .and_then(|value| value.parse().ok())
+                        .unwrap_or(1),
+                    name: card_name,
+                    set: field(set),
+                    collector_number: field(collector_number),
+                    mtgo_id: None,
+                    scryfall_id: field(scryfall_id),
+                    finish: field(finish)
+                        .map(|value| parse_finish(&value))
+                        .unwrap_or(Finish::Nonfoil),
+                    tags,
+                    zone,
+                },
+                condition: field(condition),
+                language: field(language),
+                location: field(location),
+            });
+        }
+
+        Ok(records)
+    }
+
+    pub fn export(&self, deck: &Deck) -> Result<String> {
+        let columns = self.columns();
+        let mut writer = ::csv::Writer::from_writer(Vec::new());
+        writer.write_record(&columns)?;
+
+        for entry in Zone::ALL.iter().flat_map(|zone| deck.entries(*zone)) {
+            let card = &entry.card;
+            // Archidekt keeps the zone with the categories in one column
+            let board_and_tags = |board: bool, tags: bool| {
+                let mut values = Vec::new();
+                if board && entry.zone != Zone::Main {
+                    values.push(entry.zone.label().to_string());
+                }
+                if tags {
+                    values.extend(entry.tags.iter().cloned());
+                }
+                values.join(",")
+            };
+
+            let row = columns
+                .iter()
+                .map(|column| {
+                    let is = |field: &Option<String>| field.as_deref() == Some(*column);
+                    if *column == self.quantity {
+                        entry.quantity.to_string()
+                    } else if *column == self.name {
+                        card.name.clone()
+                    } else if is(&self.set) {
+                        card.set_short.clone().unwrap_or_default()
+                    } else if is(&self.collector_number) {
+                        card.collector_number.clone().unwrap_or_default()
+                    } else if is(&self.scryfall_id) {
+                        card.card_id.clone()
+                    } else if is(&self.finish) {
+                        self.finish_name(&entry.finish)
+                    } else if is(&self.board) || is(&self.tags) {
+                        board_and_tags(is(&self.board), is(&self.tags))
+                    } else {
+                        String::new()
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            writer.write_record(&row)?;
+        }
+
+        let data = writer.into_inner().context("writing CSV")?;
+        Ok(String::from_utf8(data)?)
+    }
+
+    pub fn export_collection(&self, collection: &[CollectionEntry]) -> Result<String> {
+        let columns = self.columns();
+        let mut writer = ::csv::Writer::from_writer(Vec::new());
+        writer.write_record(&columns)?;
+
+        for entry in collection {
+            let card = &entry.card;
+            let row = columns
+                .iter()
+                .map(|column| {
+                    let is = |field: &Option<String>| field.as_deref() == Some(*column);
+                    let value = |value: &Option<String>| value.clone().unwrap_or_default();
+                    if *column == self.quantity {
+                        entry.quantity.to_string()
+                    } else if *column == self.name {
+                        card.name.clone()
+                    } else if is(&self.set) {
+                        value(&card.set_short)
+                    } else if is(&self.collector_number) {
+                        value(&card.collector_number)
+                    } else if is(&self.scryfall_id) {
+                        card.card_id.clone()
+                    } else if is(&self.finish) {
+                        self.finish_name(&entry.finish)
+                    } else if is(&self.condition) {
+                        value(&entry.condition)
+                    } else if is(&self.language) {
+                        value(&entry.language)
+                    } else if is(&self.location) {
+                        value(&entry.location)
+                    } else {
+                        String::new()
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            writer.write_record(&row)?;
+        }
+
+        let data = writer.into_inner().context("writing CSV")?;
+        Ok(String::from_utf8(data)?)
+    }
+}
+
+/// Site whose columns match the header row
+fn detect(input: &str, fallback: CsvSite) -> CsvSite {
+    let header = input
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    CsvSite::ALL
+        .into_iter()
+        .find(|site| {
+            let mapping = site.mapping();
+            [&Some(mapping.quantity), &mapping.set]
+                .into_iter()
+                .flatten()
+                .all(|column| header.contains(&column.to_lowercase()))
+        })
+        .unwrap_or(fallback)
+}
+
+/// Parse a CSV, picking the mapping whose columns match the header row
+pub fn parse(input: &str, fallback: CsvSite) -> Result<Decklist> {
+    let lines = detect(input, fallback)
+        .mapping()
+        .parse(input)?
+        .into_iter()
+        .map(|record| record.line)
+        .collect();
+
+    Ok(Decklist { name: None, lines })
+}
+
+/// Parse a collection CSV, keeping each row's finish, condition, language and location
+pub fn parse_collection(input: &str, fallback: CsvSite) -> Result<Vec<CsvRecord>> {
+    detect(input, fallback).mapping().parse(input)
+}
+
+#[cfg(test)]
+mod csv_tests {
+    use super::*;
+    use crate::decklist::{assert_round_trip, test_deck};
+
+    #[test]
+    fn export_round_trip() -> Result<()> {
+        let mut deck = test_deck();
+        deck.cards[0].zone = Zone::Commander;
+
+        let exported = CsvSite::Archidekt.mapping().export(&deck)?;
+        assert_eq!(exported, include_str!("../../testdata/burn.csv"));
+        assert_round_trip(&deck, &parse(&exported, CsvSite::Moxfield)?, true);
+
+        deck.cards[1].finish = Finish::Foil;
+        deck.cards[2].finish = Finish::Etched;
+        for site in [CsvSite::Moxfield, CsvSite::ManaBox] {
+            // Without a board column everything comes back in the main deck
+            let decklist = parse(&site.mapping().export(&deck)?, CsvSite::Archidekt)?;
+            assert_eq!(decklist.lines.len(), 3);
+            assert!(decklist.lines.iter().all(|line| line.zone == Zone::Main));
+            assert_eq!(decklist.lines[2].collector_number.as_deref(), Some("94"));
+            assert_eq!(
+                decklist
+                    .lines
+                    .iter()
+                    .map(|line| line.finish.clone())
+                    .collect::<Vec<_>>(),
+                vec![Finish::Nonfoil, Finish::Foil, Finish::Etched]
+            );
+        }
+
+        let archidekt = CsvSite::Archidekt.mapping().export(&deck)?;
+        assert_round_trip(&deck, &parse(&archidekt, CsvSite::Moxfield)?, true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn collection_round_trip() -> Result<()> {
+        let deck = test_deck();
+        let collection = deck
+            .cards
+            .iter()
+            .map(|entry| CollectionEntry {
+                card: entry.card.clone(),
+                finish: Finish::Foil,
+                condition: Some("near_mint".to_string()),
+                language: Some("en".to_string()),
+                location: Some("Trade".to_string()),
+                quantity: entry.quantity,
+            })
+            .collect::<Vec<_>>();
+
+        let exported = CsvSite::ManaBox.mapping().export_collection(&collection)?;
+        let records = parse_collection(&exported, CsvSite::Moxfield)?;
+        assert_eq!(records.len(), 3);
+        for (entry, record) in collection.iter().zip(records.iter()) {
+            assert_eq!(record.line.name, entry.card.name);
+            assert_eq!(record.line.quantity, entry.quantity);
+            assert_eq!(record.line.scryfall_id.as_ref(), Some(&entry.card.card_id));
+            assert_eq!(record.line.finish, Finish::Foil);
+            assert_eq!(record.condition, entry.condition);
+            assert_eq!(record.location, entry.location);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn import_sites() -> Result<()> {
+        let manabox = "Binder Name,Binder Type,Name,Set code,Set name,Collector number,Foil,Rarity,Quantity,ManaBox ID,Scryfall ID,Purchase price,Misprint,Altered,Condition,Language,Purchase price currency\n\
+            Trade,binder,Lightning Bolt,M10,Magic 2010,146,foil,common,4,1,abc,0.5,false,false,near_mint,en,USD\n";
+        let records = CsvSite::ManaBox.mapping().parse(manabox)?;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].line.quantity, 4);
+        assert_eq!(records[0].line.set.as_deref(), Some("M10"));
+        assert_eq!(records[0].line.scryfall_id.as_deref(), Some("abc"));
+        assert_eq!(records[0].line.finish, Finish::Foil);
+        assert_eq!(records[0].condition.as_deref(), Some("near_mint"));
+        assert_eq!(records[0].location.as_deref(), Some("Trade"));
+
+        let moxfield = "\"Count\",\"Tradelist Count\",\"Name\",\"Edition\",\"Condition\",\"Language\",\"Foil\",\"Tags\",\"Last Modified\",\"Collector Number\"\n\
+            \"2\",\"0\",\"Sol Ring\",\"cmm\",\"Near Mint\",\"English\",\"etched\",\"ramp,artifact\",\"2025-01-01\",\"464\"\n";
+        let records = CsvSite::Moxfield.mapping().parse(moxfield)?;
+        assert_eq!(records[0].line.finish, Finish::Etched);
+        assert_eq!(records[0].line.tags, vec!["ramp", "artifact"]);
+        assert_eq!(parse(moxfield, CsvSite::ManaBox)?.lines[0].name, "Sol Ring");
+
+        let archidekt = "Quantity,Name,Finish,Edition Code,Collector Number,Categories\n\
+            1,Sol Ring,Normal,cmm,464,\"Ramp,Artifact\"\n\
+            1,Kenrith,Normal,eld,303,Commander\n\
+            1,Mana Crypt,Foil,2xm,270,Maybeboard\n";
+        let decklist = parse(archidekt, CsvSite::Moxfield)?;
+        let zones = decklist
+            .lines
+            .iter()
+            .map(|line| (line.name.as_str(), line.zone))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            zones,
+            vec![
+                ("Sol Ring", Zone::Main),
+                ("Kenrith", Zone::Commander),
+                ("Mana Crypt", Zone::Maybeboard)
+            ]
+        );
+        assert_eq!(decklist.lines[0].tags, vec!["Ramp", "Artifact"]);
+
+        let mut deck = test_deck();
+        deck.cards[2].tags = vec!["hate".to_string()];
+        let exported = CsvSite::Archidekt.mapping().export(&deck)?;
+        let decklist = parse(&exported, CsvSite::Archidekt)?;
+        assert_eq!(decklist.lines[2].zone, Zone::Sideboard);
+        assert_eq!(decklist.lines[2].tags, vec!["hate"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+//! Forge and XMage `.dck` files.
+//!
+//! ```text
+//! [metadata]
+//! Name=Boros Burn
+//! [Main]
+//! 4 Lightning Bolt|M10
+//! ```
+//!
+//! Forge's third `|` field is an art index rather than a collector number, so printings are
+//! only kept down to the set. Zones Forge doesn't have go in the sideboard, apart from the
+//! maybeboard which is left out.
+
+use ponder_db::{deck::Zone, scryfall::Finish};
+
+use super::{Decklist, DecklistLine};
+use crate::data::Deck;
+
+pub fn parse(input: &str) -> Decklist {
+    let mut decklist = Decklist::default();
+    let mut section = None;
+
+    for (i, line) in input.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = Some(name.to_lowercase());
+            continue;
+        }
+
+        let zone = match section.as_deref() {
+            Some("metadata") => {
+                if let Some(name) = line.strip_prefix("Name=") {
+                    decklist.name = Some(name.trim().to_string());
+                }
+                continue;
+            }
+            Some("main") => Zone::Main,
+            Some("sideboard") => Zone::Sideboard,
+            Some("commander") => Zone::Commander,
+            _ => continue,
+        };
+
+        let Some((quantity, card)) = line.split_once(' ') else {
+            continue;
+        };
+        let Ok(quantity) = quantity.parse() else {
+            continue;
+        };
+
+        let mut fields = card.split('|');
+        let name = fields.next().unwrap_or_default().trim();
+        let set = fields
+            .next()
+            .map(str::trim)
+            .filter(|set| !set.is_empty())
+            .map(str::to_string);
+
+        decklist.lines.push(DecklistLine {
+            line_number: i + 1,
+            quantity,
+            name: name.to_string(),
+            set,
+            collector_number: None,
+            mtgo_id: None,
+            scryfall_id: None,
+            finish: Finish::Nonfoil,
+            tags: Vec::new(),
+            zone,
+        });
+    }
+
+    decklist
+}
+
+pub fn export(deck: &Deck) -> String {
+    let mut out = format!("[metadata]\nName={}\n", deck.name);
+
+    for (header, zones) in [
+        ("Commander", &[Zone::Commander][..]),
+        ("Main", &[Zone::Main][..]),
+        (
+            "Sideboard",
+            &[
+                Zone::Sideboard,
+                Zone::SignatureSpell,
+                Zone::Companion,
+                Zone::Wishboard,
+                Zone::Attractions,
+                Zone::Contraptions,
+            ][..],
+        ),
+    ] {
+        let mut entries = zones.iter().flat_map(|zone| deck.entries(*zone)).peekable();
+        if entries.peek().is_none() {
+            continue;
+        }
+
+        out.push_str(&format!("[{header}]\n"));
+
+        for entry in entries {
+            out.push_str(&format!("{} {}", entry.quantity, entry.card.name));
+            if let Some(set) = &entry.card.set_short {
+                out.push_str(&format!("|{}", set.to_uppercase()));
+            }
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod forge_tests {
+    use super::*;
+    use crate::decklist::{assert_round_trip, test_deck};
+
+    #[test]
+    fn export_dck() {
+        let deck = test_deck();
+        let exported = export(&deck);
+        assert_eq!(exported, include_str!("../../testdata/burn.dck"));
+
+        let decklist = parse(&exported);
+        assert_eq!(decklist.name.as_deref(), Some("Boros Burn"));
+        assert_round_trip(&deck, &decklist, true);
+    }
+}
@@ -0,0 +1,269 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use ponder_db::{
+    SqliteStore,
+    card::Card,
+    deck::Zone,
+    scryfall::{Finish, Format},
+    search::suggest_card_names,
+};
+
+use crate::data::Deck;
+
+pub mod cockatrice;
+pub mod csv;
+pub mod forge;
+pub mod mtgo;
+pub mod text;
+
+/// Decklist file formats ponder can read and write
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DecklistFormat {
+    /// Plain text, MTGA and MTGO `.txt` lists. Exports use the MTGA layout with set codes.
+    Text,
+    /// Plain text grouped by tag, with each card's tags written after it as `#tag`
+    TaggedText,
+    /// MTGO `.dek` XML
+    Mtgo,
+    /// Cockatrice `.cod` XML
+    Cockatrice,
+    /// Forge and XMage `.dck`
+    Forge,
+    /// CSV from a deckbuilding site. The site picks the export columns, imports match the header
+    /// row and only fall back to the site when no site matches.
+    Csv(csv::CsvSite),
+}
+
+impl DecklistFormat {
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        let extension = path
+            .as_ref()
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+
+        match extension.as_deref() {
+            Some("dek") => Self::Mtgo,
+            Some("cod") => Self::Cockatrice,
+            Some("dck") => Self::Forge,
+            Some("csv") => Self::Csv(csv::CsvSite::Moxfield),
+            _ => Self::Text,
+        }
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Decklist> {
+        match self {
+            Self::Text | Self::TaggedText => Ok(text::parse(input)),
+            Self::Mtgo => mtgo::parse(input),
+            Self::Cockatrice => cockatrice::parse(input),
+            Self::Forge => Ok(forge::parse(input)),
+            Self::Csv(site) => csv::parse(input, *site),
+        }
+    }
+
+    pub fn export(&self, deck: &Deck) -> Result<String> {
+        match self {
+            Self::Text => Ok(text::export(deck)),
+            Self::TaggedText => Ok(text::export_tagged(deck)),
+            Self::Mtgo => mtgo::export(deck),
+            Self::Cockatrice => cockatrice::export(deck),
+            Self::Forge => Ok(forge::export(deck)),
+            Self::Csv(site) => site.mapping().export(deck),
+        }
+    }
+}
+
+/// A card line from a decklist, before it's resolved against the database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecklistLine {
+    pub line_number: usize,
+    pub quantity: u32,
+    pub name: String,
+    pub set: Option<String>,
+    pub collector_number: Option<String>,
+    pub mtgo_id: Option<i32>,
+    pub scryfall_id: Option<String>,
+    pub finish: Finish,
+    pub zone: Zone,
+    pub tags: Vec<String>,
+}
+
+impl DecklistLine {
+    /// Look up the card, keeping the exact printing when a set and collector number are given
+    pub async fn resolve(&self, store: &SqliteStore) -> Result<Option<Card>> {
+        let mut card = None;
+        if let Some(scryfall_id) = &self.scryfall_id {
+            card = store.card_by_scryfall_id(scryfall_id).await?;
+        }
+
+        if card.is_none()
+            && let Some(mtgo_id) = self.mtgo_id
+        {
+            card = store.card_by_mtgo_id(mtgo_id).await?;
+        }
+
+        if card.is_none()
+            && let Some(set) = &self.set
+        {
+            card = match &self.collector_number {
+                Some(number) => store.card_by_set_and_number(set, number).await?,
+                None => store.card_by_name_in_set(&self.name, set).await?,
+            };
+        }
+
+        if card.is_none() {
+            card = store.card_by_name(&self.name).await?;
+        }
+
+        Ok(card)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Decklist {
+    pub name: Option<String>,
+    pub lines: Vec<DecklistLine>,
+}
+
+/// A line that didn't match any card, with the closest card names
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unresolved {
+    pub line: DecklistLine,
+    pub suggestions: Vec<String>,
+}
+
+impl Unresolved {
+    /// Suggest names for every line that didn't match, loading the card names only once
+    pub async fn suggest(store: &SqliteStore, lines: Vec<DecklistLine>) -> Result<Vec<Self>> {
+        if lines.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let names = store.card_names().await?;
+        Ok(lines
+            .into_iter()
+            .map(|line| Self {
+                suggestions: suggest_card_names(&names, &line.name, 3),
+                line,
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug)]
+pub struct ImportReport {
+    pub deck: Deck,
+    pub unresolved: Vec<Unresolved>,
+}
+
+impl Decklist {
+    /// Commander if the list has a commander section, otherwise Vintage as it allows the most cards
+    pub fn default_format(&self) -> Format {
+        if self.lines.iter().any(|line| line.zone == Zone::Commander) {
+            Format::Commander
+        } else {
+            Format::Vintage
+        }
+    }
+
+    /// Look up every line into a deck, listing the lines that didn't match a card
+    pub async fn resolve(
+        &self,
+        store: &SqliteStore,
+        name: &str,
+        format: Format,
+    ) -> Result<ImportReport> {
+        let mut deck = Deck::new(self.name.as_deref().unwrap_or(name), format);
+        let mut unresolved = Vec::new();
+
+        for line in self.lines.iter() {
+            match line.resolve(store).await? {
+                Some(card) => {
+                    let entry = deck.add(card, line.quantity, line.zone, line.finish.clone());
+                    for tag in line.tags.iter() {
+                        if !entry.tags.contains(tag) {
+                            entry.tags.push(tag.clone());
+                        }
+                    }
+                }
+                None => unresolved.push(line.clone()),
+            }
+        }
+
+        deck.update_colors();
+        let unresolved = Unresolved::suggest(store, unresolved).await?;
+        Ok(ImportReport { deck, unresolved })
+    }
+}
+
+/// Parse a decklist file and resolve it into a deck named after the file
+pub async fn import_file(
+    store: &SqliteStore,
+    path: impl AsRef<Path>,
+    format: Option<Format>,
+) -> Result<ImportReport> {
+    let path = path.as_ref();
+    let data = std::fs::read_to_string(path)
+        .with_context(|| format!("reading decklist - {}", path.display()))?;
+
+    let decklist = DecklistFormat::from_path(path)
+        .parse(&data)
+        .with_context(|| format!("parsing decklist - {}", path.display()))?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Imported deck".to_string());
+    let format = format.unwrap_or_else(|| decklist.default_format());
+
+    decklist.resolve(store, &name, format).await
+}
+
+/// Write a deck to a file, picking the format from its extension
+pub fn export_file(deck: &Deck, path: impl AsRef<Path>) -> Result<()> {
+    export_file_as(deck, &path, DecklistFormat::from_path(&path))
+}
+
+pub fn export_file_as(deck: &Deck, path: impl AsRef<Path>, format: DecklistFormat) -> Result<()> {
+    let path = path.as_ref();
+    let data = format.export(deck)?;
+    std::fs::write(path, data).with_context(|| format!("writing decklist - {}", path.display()))
+}
+
+#[cfg(test)]
+pub(crate) fn test_deck() -> Deck {
+    use crate::data::test_card;
+
+    let mut deck = Deck::new("Boros Burn", Format::Modern);
+    for (id, name, set, number, mtgo_id, quantity, zone) in [
+        (1, "Lightning Bolt", "m10", "146", 31_683, 4, Zone::Main),
+        (2, "Goblin Guide", "zen", "126", 35_938, 4, Zone::Main),
+        (3, "Duress", "m19", "94", 68_390, 2, Zone::Sideboard),
+    ] {
+        let mut card = test_card(id, name);
+        card.card_id = name.to_lowercase().replace(' ', "-");
+        card.set_short = Some(set.to_string());
+        card.collector_number = Some(number.to_string());
+        card.mtgo_id = Some(mtgo_id);
+        deck.add(card, quantity, zone, Finish::Nonfoil);
+    }
+
+    deck
+}
+
+/// Check a parsed list has the same cards, printings and zones as the deck it was exported from
+#[cfg(test)]
+pub(crate) fn assert_round_trip(deck: &Deck, decklist: &Decklist, printings: bool) {
+    assert_eq!(deck.cards.len(), decklist.lines.len());
+    for (entry, line) in deck.cards.iter().zip(decklist.lines.iter()) {
+        assert_eq!(entry.card.name, line.name);
+        assert_eq!(entry.quantity, line.quantity);
+        assert_eq!(entry.zone, line.zone);
+        assert_eq!(entry.finish, line.finish);
+        if printings {
+            assert_eq!(
+                entry.card.set_short.as_deref().map(str::to_uppercase),
+                line.set.as_deref().map(str::to_uppercase)
+            );
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! MTGO `.dek` XML. Cards are identified by their MTGO catalog id, falling back to the name.
+//!
+//! MTGO only has a main deck and sideboard, so commanders and the other zones go in the
+//! sideboard and the maybeboard is left out.
+
+use anyhow::{Context, Result};
+use ponder_db::{deck::Zone, scryfall::Finish};
+use quick_xml::se::Serializer;
+use serde::{Deserialize, Serialize};
+
+use super::{Decklist, DecklistLine};
+use crate::data::Deck;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename = "Deck")]
+struct DekFile {
+    #[serde(rename = "@xmlns:xsd", default)]
+    xsd: String,
+    #[serde(rename = "@xmlns:xsi", default)]
+    xsi: String,
+    #[serde(rename = "NetDeckID", default)]
+    net_deck_id: i64,
+    #[serde(rename = "PreconstructedDeckID", default)]
+    preconstructed_deck_id: i64,
+    #[serde(rename = "Cards", default)]
+    cards: Vec<DekCard>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DekCard {
+    #[serde(rename = "@CatID", default)]
+    cat_id: i32,
+    #[serde(rename = "@Quantity")]
+    quantity: u32,
+    #[serde(rename = "@Sideboard", default)]
+    sideboard: bool,
+    #[serde(rename = "@Name")]
+    name: String,
+    #[serde(rename = "@Annotation", default)]
+    annotation: i32,
+}
+
+pub fn parse(input: &str) -> Result<Decklist> {
+    let dek: DekFile = quick_xml::de::from_str(input).context("parsing MTGO .dek")?;
+    let lines = dek
+        .cards
+        .into_iter()
+        .enumerate()
+        .map(|(i, card)| DecklistLine {
+            line_number: i + 1,
+            quantity: card.quantity,
+            name: card.name,
+            set: None,
+            collector_number: None,
+            mtgo_id: (card.cat_id > 0).then_some(card.cat_id),
+            scryfall_id: None,
+            finish: Finish::Nonfoil,
+            tags: Vec::new(),
+            zone: if card.sideboard {
+                Zone::Sideboard
+            } else {
+                Zone::Main
+            },
+        })
+        .collect();
+
+    Ok(Decklist { name: None, lines })
+}
+
+pub fn export(deck: &Deck) -> Result<String> {
+    let cards = Zone::ALL
+        .iter()
+        .filter(|zone| zone.is_played())
+        .flat_map(|zone| deck.entries(*zone))
+        .map(|entry| DekCard {
+            cat_id: entry.card.mtgo_id.unwrap_or_default(),
+            quantity: entry.quantity,
+            sideboard: entry.zone != Zone::Main,
+            name: entry.card.name.clone(),
+            annotation: 0,
+        })
+        .collect();
+
+    let dek = DekFile {
+        xsd: "http://www.w3.org/2001/XMLSchema".to_string(),
+        xsi: "http://www.w3.org/2001/XMLSchema-instance".to_string(),
+        net_deck_id: 0,
+        preconstructed_deck_id: 0,
+        cards,
+    };
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    let mut serializer = Serializer::new(&mut out);
+    serializer.indent(' ', 2);
+    dek.serialize(serializer).context("writing MTGO .dek")?;
+    out.push('\n');
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod mtgo_tests {
+    use super::*;
+    use crate::decklist::{assert_round_trip, test_deck};
+
+    #[test]
+    fn export_dek() -> Result<()> {
+        let deck = test_deck();
+        let exported = export(&deck)?;
+        assert_eq!(exported, include_str!("../../testdata/burn.dek"));
+
+        let decklist = parse(&exported)?;
+        assert_round_trip(&deck, &decklist, false);
+        assert_eq!(decklist.lines[0].mtgo_id, Some(31_683));
+
+        Ok(())
+    }
+}
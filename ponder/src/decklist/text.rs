@@ -0,0 +1,440 @@
+//! Plain text decklists, including MTGA and MTGO exports.
+//!
+//! Lines look like `4 Lightning Bolt`, `4x Lightning Bolt` or `4 Lightning Bolt (M10) 146`,
+//! with MTGA's `*F*` and `*E*` marking foil and etched copies.
+//! Sections start with headers such as `Deck`, `Sideboard` or `Commander`, or an `SB:` prefix.
+//! Without any headers a blank line after the main deck starts the sideboard, as MTGO does.
+//! Tags follow the card as `#ramp #draw`, as Moxfield writes them, or `[Ramp,Draw]` as
+//! Archidekt does.
+
+use ponder_db::{deck::Zone, scryfall::Finish};
+
+use super::{Decklist, DecklistLine};
+use crate::data::{Deck, DeckEntry};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Section {
+    Cards(Zone),
+    About,
+    Skipped,
+}
+
+fn header(line: &str) -> Option<Section> {
+    let line = line.trim_start_matches("//").trim().trim_end_matches(':');
+    let section = match line.to_lowercase().as_str() {
+        "deck" | "main" | "mainboard" | "main deck" => Section::Cards(Zone::Main),
+        "sideboard" | "side" => Section::Cards(Zone::Sideboard),
+        "commander" | "commanders" => Section::Cards(Zone::Commander),
+        "companion" => Section::Cards(Zone::Companion),
+        "maybeboard" | "considering" => Section::Cards(Zone::Maybeboard),
+        "signature spell" | "signature spells" => Section::Cards(Zone::SignatureSpell),
+        "attractions" => Section::Cards(Zone::Attractions),
+        "contraptions" => Section::Cards(Zone::Contraptions),
+        "wishboard" => Section::Cards(Zone::Wishboard),
+        "about" => Section::About,
+        "tokens" => Section::Skipped,
+        _ => return None,
+    };
+
+    Some(section)
+}
+
+/// Split `Lightning Bolt (M10) 146` into the name, set and collector number
+fn split_printing(rest: &str) -> (&str, Option<String>, Option<String>) {
+    if let Some(open) = rest.rfind(" (")
+        && let Some(close) = rest[open..].find(')')
+    {
+        let close = open + close;
+        let set = &rest[open + 2..close];
+        let number = rest[close + 1..].trim();
+        if !set.is_empty() && !set.contains(' ') && !number.contains(' ') {
+            return (
+                rest[..open].trim(),
+                Some(set.to_string()),
+                (!number.is_empty()).then(|| number.to_string()),
+            );
+        }
+    }
+
+    (rest.trim(), None, None)
+}
+
+/// Split the Moxfield `#tag` and Archidekt `[Tag]` tags off the end of a line.
+///
+/// Archidekt puts the zone in with the categories, so a category naming a zone is returned
+/// as the zone rather than a tag.
+fn split_tags(line: &str) -> (&str, Vec<String>, Option<Zone>) {
+    let mut rest = line;
+    let mut tags = Vec::new();
+    let mut zone = None;
+
+    if rest.ends_with(']')
+        && let Some(open) = rest.rfind('[')
+    {
+        for category in rest[open + 1..rest.len() - 1].split(',') {
+            // Archidekt marks categories left out of the deck as `{noDeck}`
+            let category = category.split('{').next().unwrap_or_default().trim();
+            match header(category) {
+                Some(Section::Cards(category_zone)) => zone = Some(category_zone),
+                _ if !category.is_empty() => tags.push(category.to_string()),
+                _ => {}
+            }
+        }
+        rest = rest[..open].trim_end();
+    }
+
+    if let Some(start) = rest.find(" #") {
+        tags.extend(
+            rest[start..]
+                .split(" #")
+                .map(|tag| tag.trim().trim_start_matches('!'))
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string),
+        );
+        rest = rest[..start].trim_end();
+    }
+
+    (rest, tags, zone)
+}
+
+/// The finish from MTGA's `*F*` foil and `*E*` etched markers
+fn marked_finish(line: &str) -> Finish {
+    if line.contains("*F*") {
+        Finish::Foil
+    } else if line.contains("*E*") {
+        Finish::Etched
+    } else {
+        Finish::Nonfoil
+    }
+}
+
+fn parse_card(line: &str) -> Option<(u32, &str, Option<String>, Option<String>)> {
+    let mut rest = line;
+    for marker in ["*F*", "*E*", "*CMDR*"] {
+        rest = rest.trim_end().trim_end_matches(marker);
+    }
+
+    let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+    let quantity = if digits > 0 {
+        let quantity = rest[..digits].parse().ok()?;
+        rest = rest[digits..]
+            .strip_prefix(['x', 'X'])
+            .unwrap_or(&rest[digits..]);
+        if !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+
+        quantity
+    } else {
+        1
+    };
+
+    let (name, set, number) = split_printing(rest.trim());
+    (!name.is_empty()).then_some((quantity, name, set, number))
+}
+
+pub fn parse(input: &str) -> Decklist {
+    let mut decklist = Decklist::default();
+    let mut section = Section::Cards(Zone::Main);
+    let mut seen_header = false;
+
+    for (i, line) in input.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            let after_main = section == Section::Cards(Zone::Main);
+            if !seen_header && after_main && !decklist.lines.is_empty() {
+                section = Section::Cards(Zone::Sideboard);
+            }
+            continue;
+        }
+
+        if let Some(next) = header(line) {
+            section = next;
+            seen_header = true;
+            continue;
+        }
+
+        if line.starts_with("//") || line.starts_with('#') {
+            continue;
+        }
+
+        let zone = match section {
+            Section::Cards(zone) => zone,
+            Section::About => {
+                if let Some(name) = line.strip_prefix("Name ") {
+                    decklist.name = Some(name.trim().to_string());
+                }
+                continue;
+            }
+            Section::Skipped => continue,
+        };
+
+        let (line, zone) = match line.strip_prefix("SB:") {
+            Some(rest) => (rest.trim(), Zone::Sideboard),
+            None => (line, zone),
+        };
+
+        let (line, tags, tag_zone) = split_tags(line);
+        let zone = tag_zone.unwrap_or(zone);
+        if let Some((quantity, name, set, collector_number)) = parse_card(line) {
+            decklist.lines.push(DecklistLine {
+                line_number: i + 1,
+                quantity,
+                name: name.to_string(),
+                set,
+                collector_number,
+                mtgo_id: None,
+                scryfall_id: None,
+                finish: marked_finish(line),
+                tags,
+                zone,
+            });
+        }
+    }
+
+    decklist
+}
+
+fn push_entry(out: &mut String, entry: &DeckEntry, tags: bool) {
+    out.push_str(&format!("{} {}", entry.quantity, entry.card.name));
+    if let Some(set) = &entry.card.set_short {
+        out.push_str(&format!(" ({})", set.to_uppercase()));
+        if let Some(number) = &entry.card.collector_number {
+            out.push_str(&format!(" {number}"));
+        }
+    }
+
+    match entry.finish {
+        Finish::Foil => out.push_str(" *F*"),
+        Finish::Etched => out.push_str(" *E*"),
+        _ => {}
+    }
+
+    if tags {
+        for tag in entry.tags.iter() {
+            out.push_str(&format!(" #{tag}"));
+        }
+    }
+    out.push('\n');
+}
+
+fn export_zones(deck: &Deck, tagged: bool) -> String {
+    let mut out = format!("About\nName {}\n", deck.name);
+
+    for zone in Zone::ALL {
+        let mut entries = deck.entries(zone).collect::<Vec<_>>();
+        if entries.is_empty() {
+            continue;
+        }
+
+        let header = match zone {
+            Zone::Main => "Deck",
+            zone => zone.label(),
+        };
+        out.push_str(&format!("\n{header}\n"));
+
+        if !tagged {
+            for entry in entries {
+                push_entry(&mut out, entry, false);
+            }
+            continue;
+        }
+
+        // Each card is listed once, under its first tag
+        entries.sort_by_key(|entry| (entry.tags.is_empty(), entry.tags.first().cloned()));
+        for group in entries.chunk_by(|a, b| a.tags.first() == b.tags.first()) {
+            let tag = group[0].tags.first().map_or("untagged", String::as_str);
+            let count = group
+                .iter()
+                .map(|entry| entry.quantity as usize)
+                .sum::<usize>();
+            out.push_str(&format!("# {tag} ({count})\n"));
+            for entry in group {
+                push_entry(&mut out, entry, true);
+            }
+        }
+    }
+
+    out
+}
+
+/// Export in the MTGA layout, keeping each card's set code and collector number
+pub fn export(deck: &Deck) -> String {
+    export_zones(deck, false)
+}
+
+/// Export in the MTGA layout with each zone grouped by the cards' first tag
+pub fn export_tagged(deck: &Deck) -> String {
+    export_zones(deck, true)
+}
+
+#[cfg(test)]
+mod text_tests {
+    use super::*;
+    use crate::decklist::{assert_round_trip, test_deck};
+
+    type Summary<'a> = (u32, &'a str, Option<&'a str>, Option<&'a str>, Zone);
+
+    fn summary(decklist: &Decklist) -> Vec<Summary<'_>> {
+        decklist
+            .lines
+            .iter()
+            .map(|line| {
+                (
+                    line.quantity,
+                    line.name.as_str(),
+                    line.set.as_deref(),
+                    line.collector_number.as_deref(),
+                    line.zone,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn arena() {
+        let decklist = parse(
+            "About\nName Boros Burn\n\nCommander\n1 Kari Zev, Ravnica's Firebrand (WAR) 141\n\nDeck\n4 Lightning Bolt (M10) 146\n1 Fire // Ice (MH2) 290 *F*\n\nSideboard\n2 Duress (M19)\n",
+        );
+
+        assert_eq!(decklist.name.as_deref(), Some("Boros Burn"));
+        assert_eq!(
+            summary(&decklist),
+            vec![
+                (
+                    1,
+                    "Kari Zev, Ravnica's Firebrand",
+                    Some("WAR"),
+                    Some("141"),
+                    Zone::Commander
+                ),
+                (4, "Lightning Bolt", Some("M10"), Some("146"), Zone::Main),
+                (1, "Fire // Ice", Some("MH2"), Some("290"), Zone::Main),
+                (2, "Duress", Some("M19"), None, Zone::Sideboard),
+            ]
+        );
+        assert_eq!(decklist.lines[1].line_number, 8);
+        assert_eq!(decklist.lines[1].finish, Finish::Nonfoil);
+        assert_eq!(decklist.lines[2].finish, Finish::Foil);
+    }
+
+    #[test]
+    fn mtgo() {
+        let decklist = parse("4 Lightning Bolt\n20 Mountain\n\n3 Smash to Smithereens\n");
+        assert_eq!(
+            summary(&decklist),
+            vec![
+                (4, "Lightning Bolt", None, None, Zone::Main),
+                (20, "Mountain", None, None, Zone::Main),
+                (3, "Smash to Smithereens", None, None, Zone::Sideboard),
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_text() {
+        let decklist = parse(
+            "// Burn\n4x Lightning Bolt\nGoblin Guide\nSB: 2x Duress\n\n# more burn\n2 Erase (Not the Urza's Legacy One)\n\nMaybeboard\n1 Skewer the Critics\n",
+        );
+        assert_eq!(
+            summary(&decklist),
+            vec![
+                (4, "Lightning Bolt", None, None, Zone::Main),
+                (1, "Goblin Guide", None, None, Zone::Main),
+                (2, "Duress", None, None, Zone::Sideboard),
+                (
+                    2,
+                    "Erase (Not the Urza's Legacy One)",
+                    None,
+                    None,
+                    Zone::Sideboard
+                ),
+                (1, "Skewer the Critics", None, None, Zone::Maybeboard),
+            ]
+        );
+    }
+
+    #[test]
+    fn tags() {
+        let decklist = parse(
+            "1 Sol Ring (C21) 263 #ramp #!Mana Rock\n1x Kenrith, the Returned King (ELD) 303 [Commander{top}]\n1x Arcane Signet [Ramp,Artifact{noDeck}]\n",
+        );
+        let tags = decklist
+            .lines
+            .iter()
+            .map(|line| (line.name.as_str(), line.tags.clone(), line.zone))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tags,
+            vec![
+                (
+                    "Sol Ring",
+                    vec!["ramp".to_string(), "Mana Rock".to_string()],
+                    Zone::Main
+                ),
+                ("Kenrith, the Returned King", vec![], Zone::Commander),
+                (
+                    "Arcane Signet",
+                    vec!["Ramp".to_string(), "Artifact".to_string()],
+                    Zone::Main
+                ),
+            ]
+        );
+        assert_eq!(decklist.lines[0].set.as_deref(), Some("C21"));
+    }
+
+    #[test]
+    fn export_tags() {
+        let mut deck = test_deck();
+        deck.cards[0].tags = vec!["removal".to_string(), "burn".to_string()];
+        deck.cards[1].tags = vec!["creature".to_string()];
+
+        let exported = export_tagged(&deck);
+        assert_eq!(
+            exported,
+            "About\nName Boros Burn\n\nDeck\n# creature (4)\n4 Goblin Guide (ZEN) 126 #creature\n# removal (4)\n4 Lightning Bolt (M10) 146 #removal #burn\n\nSideboard\n# untagged (2)\n2 Duress (M19) 94\n"
+        );
+
+        let decklist = parse(&exported);
+        assert_eq!(decklist.lines.len(), 3);
+        assert_eq!(decklist.lines[1].name, "Lightning Bolt");
+        assert_eq!(decklist.lines[1].tags, deck.cards[0].tags);
+        assert_eq!(decklist.lines[2].zone, Zone::Sideboard);
+    }
+
+    #[test]
+    fn export_arena() {
+        let deck = test_deck();
+        let exported = export(&deck);
+        assert_eq!(exported, include_str!("../../testdata/burn.txt"));
+        assert_round_trip(&deck, &parse(&exported), true);
+    }
+
+    #[test]
+    fn large_piles() {
+        let mut deck = Deck::new("Rats", ponder_db::scryfall::Format::Vintage);
+        let rats = crate::data::test_card(1, "Relentless Rats");
+        deck.add(rats.clone(), 300, Zone::Main, Finish::Nonfoil);
+        deck.add(rats, 20, Zone::Main, Finish::Nonfoil);
+        assert_eq!(deck.cards[0].quantity, 320);
+        assert_round_trip(&deck, &parse(&export(&deck)), false);
+    }
+
+    #[test]
+    fn mixed_finishes() {
+        let decklist = parse("4 Sol Ring (CMM) 464\n1 Sol Ring (CMM) 464 *F*\n");
+        let mut deck = Deck::new("Rings", ponder_db::scryfall::Format::Vintage);
+        for line in decklist.lines.iter() {
+            let ring = crate::data::test_card(1, "Sol Ring");
+            deck.add(ring, line.quantity, line.zone, line.finish.clone());
+        }
+
+        let copies = deck
+            .cards
+            .iter()
+            .map(|entry| (entry.quantity, entry.finish.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(copies, vec![(4, Finish::Nonfoil), (1, Finish::Foil)]);
+        assert_round_trip(&deck, &parse(&export(&deck)), false);
+    }
+}
@@ -4,7 +4,9 @@ use anyhow::{Context, Result};
 use dotstore;
 use ponder_db::SqliteStore;
 
+mod cli;
 mod data;
+mod decklist;
 mod tui;
 use tui::Tui;
 
@@ -30,6 +32,26 @@ impl Ponder {
 #[tokio::main]
 async fn main() -> Result<()> {
     let ponder = Ponder::new().await?;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Some(command) = args.first() {
+        return match command.as_str() {
+            "import" => cli::import(&ponder, &args[1..]).await,
+            "export" => cli::export(&ponder, &args[1..]).await,
+            "history" => cli::history(&ponder, &args[1..]).await,
+            "diff" => cli::diff(&ponder, &args[1..]).await,
+            "restore" => cli::restore(&ponder, &args[1..]).await,
+            "branch" => cli::branch(&ponder, &args[1..]).await,
+            "tag" => cli::tag(&ponder, &args[1..]).await,
+            "recommend" => cli::recommend(&ponder, &args[1..]).await,
+            "lands" => cli::lands(&ponder, &args[1..]).await,
+            "manabase" => cli::manabase(&ponder, &args[1..]).await,
+            "bracket" => cli::bracket(&ponder, &args[1..]).await,
+            "cost" => cli::cost(&ponder, &args[1..]).await,
+            "collection" => cli::collection(&ponder, &args[1..]).await,
+            _ => anyhow::bail!("unknown command - {command}"),
+        };
+    }
+
     let mut tui = Tui::new(&ponder);
     if let Err(e) = tui.run().await {
         drop(tui);
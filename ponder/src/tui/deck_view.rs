@@ -0,0 +1,592 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ratatui::{
+    Frame,
+    crossterm::event::{self, Event, KeyCode},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{BarChart, Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+};
+
+use super::{AppMode, AppState, Component, EventResult};
+use crate::Ponder;
+use crate::data::{
+    Deck,
+    collection::{CollectionStatus, Ownership},
+    manabase::{self, ManaBaseReport},
+    probability::{Category, odds_by_turn},
+    stats::{CURVE_MAX, DeckStats},
+    validate::{FunctionalDuplicate, Violation, functional_duplicates},
+};
+use ponder_db::{deck::Zone, similar::SimilarCard};
+
+/// What the text prompt on the deck view is asking for
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ViewPrompt {
+    AddTag,
+    RemoveTag,
+    Odds,
+}
+
+/// Turns the draw odds are shown for
+const ODDS_TURNS: u32 = 6;
+
+/// Spells listed from the mana base report, hardest to cast first
+const MANABASE_SPELLS: usize = 5;
+
+/// Cards listed when finding ones like the selected card
+const SIMILAR_LIMIT: usize = 10;
+
+/// Draw odds by turn for a category, on the play and on the draw
+#[derive(Debug)]
+struct OddsTable {
+    label: String,
+    play: Vec<f64>,
+    draw: Vec<f64>,
+}
+
+/// A line of the deck view, pointing at its entry when it shows a card
+#[derive(Debug)]
+struct Row {
+    text: String,
+    entry: Option<usize>,
+    /// Breaks a deck construction rule
+    flagged: bool,
+}
+
+/// A single deck with each zone grouped by tag
+#[derive(Debug)]
+pub struct DeckView<'a> {
+    app: &'a Ponder,
+    mode: AppMode,
+    deck: Option<Deck>,
+    stats: DeckStats,
+    violations: Vec<Violation>,
+    /// Shown on their rows without flagging them, they aren't violations
+    duplicates: Vec<FunctionalDuplicate>,
+    odds: Option<OddsTable>,
+    manabase: Option<ManaBaseReport>,
+    /// Cards like the one named, e.g. to replace it
+    similar: Option<(String, Vec<SimilarCard>)>,
+    /// Entry shown in the card details, with the cards that play the same as it
+    details: Option<(usize, Vec<String>)>,
+    collection: CollectionStatus,
+    rows: Vec<Row>,
+    row_list: ListState,
+    prompt: ViewPrompt,
+    input: String,
+    status: Option<String>,
+}
+
+impl<'a> DeckView<'a> {
+    pub fn new(ponder: &'a Ponder) -> Self {
+        Self {
+            app: ponder,
+            mode: AppMode::Normal,
+            deck: None,
+            stats: DeckStats::default(),
+            violations: Vec::new(),
+            duplicates: Vec::new(),
+            odds: None,
+            manabase: None,
+            similar: None,
+            details: None,
+            collection: CollectionStatus::default(),
+            rows: Vec::new(),
+            row_list: ListState::default(),
+            prompt: ViewPrompt::AddTag,
+            input: String::new(),
+            status: None,
+        }
+    }
+
+    pub async fn open(&mut self, deck: Deck, violations: Vec<Violation>) -> Result<()> {
+        self.stats = deck.stats(&self.app.store).await?;
+        self.violations = violations;
+        self.duplicates = functional_duplicates(&deck);
+        self.collection = deck.collection_status(&self.app.store).await?;
+        self.deck = Some(deck);
+        self.odds = None;
+        self.manabase = None;
+        self.similar = None;
+        self.details = None;
+        self.mode = AppMode::Normal;
+        self.status = None;
+        self.row_list = ListState::default();
+        self.build_rows();
+        self.select_entry(true);
+        Ok(())
+    }
+
+    pub fn deck(&self) -> Option<&Deck> {
+        self.deck.as_ref()
+    }
+
+    fn render_stats(&self, frame: &mut Frame, area: Rect) {
+        const LABELS: [&str; CURVE_MAX + 1] = ["0", "1", "2", "3", "4", "5", "6", "7+"];
+
+        let stats = &self.stats;
+        let curve = LABELS
+            .iter()
+            .zip(stats.curve.iter())
+            .map(|(label, bucket)| (*label, bucket.total() as u64))
+            .collect::<Vec<_>>();
+        let chart = BarChart::default()
+            .block(Block::default().title("Curve").borders(Borders::ALL))
+            .data(&curve)
+            .bar_width(3);
+
+        let join = |counts: &[(String, usize)]| {
+            counts
+                .iter()
+                .map(|(name, count)| format!("{name} {count}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let colors = stats
+            .colors
+            .iter()
+            .map(|balance| format!("{:?} {}/{}", balance.color, balance.pips, balance.sources))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let permanents = stats.curve.iter().map(|b| b.permanents).sum::<usize>();
+        let spells = stats.curve.iter().map(|b| b.spells).sum::<usize>();
+
+        let mut text = [
+            format!(
+                "Average mana value {:.2} ({:.2} with lands)",
+                stats.average_mana_value_nonland, stats.average_mana_value
+            ),
+            format!(
+                "{} lands, {permanents} permanents, {spells} spells",
+                stats.lands
+            ),
+            format!("Pips/sources: {colors}"),
+            format!("Types: {}", join(&stats.types)),
+            format!("Keywords: {}", join(&stats.keywords)),
+        ]
+        .join("\n");
+
+        // Card violations are shown on their rows
+        let deck_issues = self
+            .violations
+            .iter()
+            .filter(|violation| violation.card().is_none())
+            .collect::<Vec<_>>();
+        if !deck_issues.is_empty() {
+            text.push_str("\n\nIssues");
+            for violation in deck_issues {
+                text.push_str(&format!("\n{violation}"));
+            }
+        }
+
+        if let Some(odds) = &self.odds {
+            let turns = |odds: &[f64]| {
+                odds.iter()
+                    .map(|p| format!("{:>4.0}%", p * 100.0))
+                    .collect::<String>()
+            };
+            text.push_str(&format!(
+                "\n\n{}\nTurn {}\nPlay {}\nDraw {}",
+                odds.label,
+                (1..=odds.play.len())
+                    .map(|t| format!("{t:>5}"))
+                    .collect::<String>(),
+                turns(&odds.play),
+                turns(&odds.draw)
+            ));
+        }
+
+        if let Some(report) = &self.manabase {
+            text.push_str(&format!("\n\nMana base over {} games", report.games));
+            for color in &report.colors {
+                text.push_str(&format!(
+                    "\n{:?} {}/{} sources for {}{}",
+                    color.color,
+                    color.sources,
+                    color.needed,
+                    color.card,
+                    if color.is_short() { ", add more" } else { "" }
+                ));
+            }
+            for spell in report.spells.iter().take(MANABASE_SPELLS) {
+                text.push_str(&format!(
+                    "\n{:>4.0}% on curve {}",
+                    spell.on_curve * 100.0,
+                    spell.name
+                ));
+            }
+        }
+
+        if let Some((name, similar)) = &self.similar {
+            text.push_str(&format!("\n\nLike {name}"));
+            for card in similar {
+                text.push_str(&format!("\n{:>4.0}% {}", card.score * 100.0, card.name));
+            }
+        }
+
+        let [chart_area, text_area] =
+            Layout::vertical([Constraint::Length(12), Constraint::Min(1)]).areas(area);
+        frame.render_widget(chart, chart_area);
+        frame.render_widget(
+            Paragraph::new(text)
+                .wrap(Wrap { trim: true })
+                .block(Block::default().title("Stats").borders(Borders::ALL)),
+            text_area,
+        );
+    }
+
+    fn render_details(&self, frame: &mut Frame, area: Rect) {
+        let (Some(deck), Some((index, duplicates))) = (&self.deck, &self.details) else {
+            return;
+        };
+        let card = &deck.cards[*index].card;
+
+        let mut text = vec![
+            format!(
+                "{} {}",
+                card.name,
+                card.mana_cost.as_deref().unwrap_or_default()
+            ),
+            card.type_line.clone().unwrap_or_default(),
+            card.oracle_text.clone().unwrap_or_default(),
+        ];
+        if let (Some(power), Some(toughness)) = (&card.power, &card.toughness) {
+            text.push(format!("{power}/{toughness}"));
+        }
+        if !duplicates.is_empty() {
+            text.push(format!("Plays the same as {}", duplicates.join(", ")));
+        }
+
+        frame.render_widget(
+            Paragraph::new(text.join("\n"))
+                .wrap(Wrap { trim: true })
+                .block(Block::default().title("Card").borders(Borders::ALL)),
+            area,
+        );
+    }
+
+    /// Show the selected card's details, or hide them if they're already showing it
+    async fn toggle_details(&mut self) -> Result<()> {
+        let index = self.selected_entry();
+        if self.details.as_ref().map(|(i, _)| *i) == index {
+            self.details = None;
+            return Ok(());
+        }
+
+        let (Some(deck), Some(index)) = (&self.deck, index) else {
+            return Ok(());
+        };
+        let duplicates = self
+            .app
+            .store
+            .functional_duplicates(&deck.cards[index].card)
+            .await?;
+        self.details = Some((index, duplicates));
+        Ok(())
+    }
+
+    fn build_rows(&mut self) {
+        self.rows.clear();
+        let Some(deck) = &self.deck else {
+            return;
+        };
+
+        for zone in Zone::ALL {
+            let count = deck.count(zone);
+            if count == 0 {
+                continue;
+            }
+
+            self.rows.push(Row {
+                text: format!("{} ({count})", zone.label()),
+                entry: None,
+                flagged: false,
+            });
+
+            for (tag, entries) in deck.by_tag(zone) {
+                let count = entries
+                    .iter()
+                    .map(|entry| entry.quantity as usize)
+                    .sum::<usize>();
+                self.rows.push(Row {
+                    text: format!("  {} ({count})", tag.unwrap_or("Untagged")),
+                    entry: None,
+                    flagged: false,
+                });
+
+                for entry in entries {
+                    let owned = match self.collection.get(&entry.card.name) {
+                        Some(Ownership::Owned) => " - owned".to_string(),
+                        Some(Ownership::InOtherDecks(decks)) => {
+                            format!(" - in {}", decks.join(", "))
+                        }
+                        Some(Ownership::Needed(copies)) => format!(" - need {copies}"),
+                        None => String::new(),
+                    };
+                    let issues = self
+                        .violations
+                        .iter()
+                        .filter(|violation| violation.card() == Some(entry.card.name.as_str()))
+                        .map(|violation| format!(" - {violation}"))
+                        .collect::<String>();
+                    let duplicate = self
+                        .duplicates
+                        .iter()
+                        .filter(|duplicate| duplicate.card == entry.card.name)
+                        .map(|duplicate| format!(" - {duplicate}"))
+                        .collect::<String>();
+                    self.rows.push(Row {
+                        text: format!(
+                            "    {} {}{owned}{issues}{duplicate}",
+                            entry.quantity, entry.card.name
+                        ),
+                        entry: deck.cards.iter().position(|e| std::ptr::eq(e, entry)),
+                        flagged: !issues.is_empty(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Move the selection to the next card row, skipping zone and tag headers
+    fn select_entry(&mut self, forward: bool) {
+        let start = self.row_list.selected();
+        let found = if forward {
+            let from = start.map_or(0, |i| i + 1);
+            (from..self.rows.len()).find(|i| self.rows[*i].entry.is_some())
+        } else {
+            let to = start.unwrap_or(0);
+            (0..to).rev().find(|i| self.rows[*i].entry.is_some())
+        };
+
+        if found.is_some() {
+            self.row_list.select(found);
+        }
+    }
+
+    fn selected_entry(&self) -> Option<usize> {
+        self.row_list
+            .selected()
+            .and_then(|i| self.rows.get(i))
+            .and_then(|row| row.entry)
+    }
+
+    /// Work out draw odds from a prompt like `type:Land 3 1`, the category followed by how many
+    /// cards are needed and how many mulligans were taken
+    fn calculate_odds(&mut self) -> Result<()> {
+        let input = std::mem::take(&mut self.input);
+        let Some(deck) = &self.deck else {
+            return Ok(());
+        };
+
+        let mut words = input.split_whitespace().collect::<Vec<_>>();
+        let mut numbers = Vec::new();
+        while numbers.len() < 2
+            && let Some(number) = words.last().and_then(|word| word.parse::<u32>().ok())
+        {
+            numbers.insert(0, number);
+            words.pop();
+        }
+
+        let category: Category = words.join(" ").parse()?;
+        let at_least = numbers.first().copied().unwrap_or(1);
+        let mulligans = numbers.get(1).copied().unwrap_or(0);
+        let mut label = format!("{at_least}+ {category} by turn");
+        if mulligans > 0 {
+            label.push_str(&format!(" after {mulligans} mulligans"));
+        }
+
+        self.odds = Some(OddsTable {
+            label,
+            play: odds_by_turn(deck, &category, at_least, false, mulligans, ODDS_TURNS),
+            draw: odds_by_turn(deck, &category, at_least, true, mulligans, ODDS_TURNS),
+        });
+        Ok(())
+    }
+
+    /// Find cards like the selected one, legal in the deck's format and colors
+    async fn find_similar(&mut self) -> Result<()> {
+        let (Some(deck), Some(index)) = (&self.deck, self.selected_entry()) else {
+            return Ok(());
+        };
+
+        let card = &deck.cards[index].card;
+        let similar = self
+            .app
+            .store
+            .similar_cards(card, &deck.format, deck.color_identity(), SIMILAR_LIMIT)
+            .await?;
+        self.similar = Some((card.name.clone(), similar));
+        Ok(())
+    }
+
+    /// Add or remove the tag typed into the prompt on the selected entry
+    async fn update_tag(&mut self) -> Result<()> {
+        let tag = std::mem::take(&mut self.input).trim().to_string();
+        let index = self.selected_entry();
+        let (Some(deck), Some(index)) = (self.deck.as_mut(), index) else {
+            return Ok(());
+        };
+        if tag.is_empty() {
+            return Ok(());
+        }
+
+        let deck_id = deck.id.context("deck hasn't been saved")?;
+        let entry = &mut deck.cards[index];
+        if self.prompt == ViewPrompt::AddTag {
+            self.app
+                .store
+                .add_tag(deck_id, &entry.card.card_id, entry.zone, &tag)
+                .await?;
+            if !entry.tags.contains(&tag) {
+                entry.tags.push(tag);
+            }
+        } else {
+            self.app
+                .store
+                .remove_tag(deck_id, &entry.card.card_id, entry.zone, &tag)
+                .await?;
+            entry.tags.retain(|t| *t != tag);
+        }
+
+        self.build_rows();
+
+        // Keep the same card selected now it's moved to another group
+        let row = self.rows.iter().position(|row| row.entry == Some(index));
+        self.row_list.select(row);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<'a> Component for DeckView<'a> {
+    fn render(&mut self, frame: &mut Frame) {
+        let title = self
+            .deck
+            .as_ref()
+            .map(|deck| format!("{} ({})", deck.name, deck.format))
+            .unwrap_or_default();
+
+        let items = self
+            .rows
+            .iter()
+            .map(|row| {
+                let item = ListItem::new(row.text.clone());
+                if row.entry.is_none() {
+                    item.style(Style::default().add_modifier(Modifier::BOLD))
+                } else if row.flagged {
+                    item.style(Style::default().fg(Color::Red))
+                } else {
+                    item
+                }
+            })
+            .collect::<Vec<ListItem>>();
+
+        let list = List::new(items)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        let [main, prompt] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(3)]).areas(frame.area());
+        let [cards, stats] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(main);
+        frame.render_stateful_widget(list, cards, &mut self.row_list);
+        if self.details.is_some() {
+            let [details, rest] =
+                Layout::vertical([Constraint::Percentage(40), Constraint::Min(1)]).areas(stats);
+            self.render_details(frame, details);
+            self.render_stats(frame, rest);
+        } else {
+            self.render_stats(frame, stats);
+        }
+
+        let (title, text) = match self.mode {
+            AppMode::Editing => match self.prompt {
+                ViewPrompt::AddTag => ("Add tag", self.input.clone()),
+                ViewPrompt::RemoveTag => ("Remove tag", self.input.clone()),
+                ViewPrompt::Odds => (
+                    "Draw odds: tag:, type:, mv: or cards: [at least] [mulligans]",
+                    self.input.clone(),
+                ),
+            },
+            AppMode::Normal => (
+                "",
+                self.status.clone().unwrap_or_else(|| {
+                    "i: card details, t: add tag, r: remove tag, f: find similar, p: draw odds, m: mana base, g: playtest, Esc: back".to_string()
+                }),
+            ),
+        };
+        let prompt_block =
+            Paragraph::new(text).block(Block::default().title(title).borders(Borders::ALL));
+        frame.render_widget(prompt_block, prompt);
+    }
+
+    async fn handle_event(&mut self, _event: ()) -> Result<EventResult> {
+        let Event::Key(key) = event::read()? else {
+            return Ok(EventResult::Render);
+        };
+
+        match self.mode {
+            AppMode::Normal => match key.code {
+                KeyCode::Esc => return Ok(EventResult::Switch(AppState::MainScreen)),
+                KeyCode::Down | KeyCode::Char('j') => self.select_entry(true),
+                KeyCode::Up | KeyCode::Char('k') => self.select_entry(false),
+                KeyCode::Char(c @ ('t' | 'r')) if self.selected_entry().is_some() => {
+                    self.mode = AppMode::Editing;
+                    self.prompt = if c == 't' {
+                        ViewPrompt::AddTag
+                    } else {
+                        ViewPrompt::RemoveTag
+                    };
+                    self.input.clear();
+                }
+                KeyCode::Char('g') => return Ok(EventResult::Switch(AppState::Playtest)),
+                KeyCode::Char('i') => {
+                    if let Err(e) = self.toggle_details().await {
+                        self.status = Some(format!("Loading card details failed: {e:#}"));
+                    }
+                }
+                KeyCode::Char('f') => {
+                    if let Err(e) = self.find_similar().await {
+                        self.status = Some(format!("Finding similar cards failed: {e:#}"));
+                    }
+                }
+                KeyCode::Char('m') => {
+                    self.manabase = self
+                        .deck
+                        .as_ref()
+                        .map(|deck| manabase::analyze(deck, manabase::DEFAULT_GAMES, 0));
+                }
+                KeyCode::Char('p') => {
+                    self.mode = AppMode::Editing;
+                    self.prompt = ViewPrompt::Odds;
+                    self.input.clear();
+                }
+                _ => {}
+            },
+            AppMode::Editing => match key.code {
+                KeyCode::Esc => self.mode = AppMode::Normal,
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                KeyCode::Char(c) => self.input.push(c),
+                KeyCode::Enter => {
+                    self.mode = AppMode::Normal;
+                    let result = match self.prompt {
+                        ViewPrompt::Odds => self.calculate_odds(),
+                        ViewPrompt::AddTag | ViewPrompt::RemoveTag => self.update_tag().await,
+                    };
+
+                    if let Err(e) = result {
+                        self.status = Some(format!("{:?} failed: {e:#}", self.prompt));
+                    }
+                }
+                _ => {}
+            },
+        }
+
+        Ok(EventResult::Render)
+    }
+}
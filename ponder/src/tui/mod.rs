@@ -2,18 +2,32 @@ use anyhow::{Context, Result};
 use async_trait::async_trait;
 use ratatui::{
     DefaultTerminal, Frame,
-    crossterm::event::{self, Event, KeyCode, KeyModifiers},
-    layout::*,
-    widgets::{Block, Borders, Paragraph},
+    crossterm::event::{self, Event, KeyCode},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
 };
 
 use crate::Ponder;
-use crate::data::Deck;
+use crate::data::{
+    Deck,
+    bracket::{BracketReport, estimate_bracket, load_combos},
+    commander::is_commander_format,
+    validate::Violation,
+};
+use crate::decklist;
+use deck_view::DeckView;
+use playtest::PlaytestScreen;
+use ponder_db::deck::Zone;
+
+mod deck_view;
+mod playtest;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub(crate) enum AppState {
     MainScreen,
     DeckEdit,
+    Playtest,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -22,9 +36,17 @@ pub(crate) enum AppMode {
     Editing,
 }
 
+/// What the text prompt on the main screen is asking for
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Prompt {
+    Import,
+    Export,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub(crate) enum EventResult {
     Render,
+    Switch(AppState),
     Quit,
 }
 
@@ -39,6 +61,12 @@ pub struct MainScreen<'a> {
     app: &'a Ponder,
     mode: AppMode,
     decks: Vec<Deck>,
+    violations: Vec<Vec<Violation>>,
+    brackets: Vec<Option<BracketReport>>,
+    deck_list: ListState,
+    prompt: Prompt,
+    input: String,
+    status: Option<String>,
 }
 
 impl<'a> MainScreen<'a> {
@@ -47,29 +75,195 @@ impl<'a> MainScreen<'a> {
             app: ponder,
             mode: AppMode::Normal,
             decks: Vec::new(),
+            violations: Vec::new(),
+            brackets: Vec::new(),
+            deck_list: ListState::default(),
+            prompt: Prompt::Import,
+            input: String::new(),
+            status: None,
         }
     }
 
-    pub fn load_all_decks(&self) -> Result<()> {
-        //
+    /// Import the decklist file typed into the prompt and save it as a new deck
+    async fn import_deck(&mut self) -> Result<()> {
+        let path = std::mem::take(&mut self.input);
+        let mut report = decklist::import_file(&self.app.store, path.trim(), None).await?;
+        report.deck.save(&self.app.store, Some("Imported")).await?;
+
+        let mut status = format!("Imported {}", report.deck.name);
+        if !report.unresolved.is_empty() {
+            let missing = report
+                .unresolved
+                .iter()
+                .map(|unresolved| unresolved.line.name.as_str())
+                .collect::<Vec<_>>();
+            status.push_str(&format!(", couldn't find: {}", missing.join(", ")));
+        }
+
+        self.status = Some(status);
+        self.load_all_decks().await
+    }
+
+    /// Export the selected deck to the file typed into the prompt
+    fn export_deck(&mut self) -> Result<()> {
+        let path = std::mem::take(&mut self.input);
+        let Some(deck) = self.deck_list.selected().and_then(|i| self.decks.get(i)) else {
+            return Ok(());
+        };
+
+        decklist::export_file(deck, path.trim())?;
+        self.status = Some(format!("Exported {} to {}", deck.name, path.trim()));
+        Ok(())
+    }
+
+    pub async fn load_all_decks(&mut self) -> Result<()> {
+        self.decks = self
+            .app
+            .store
+            .load_decks()
+            .await?
+            .into_iter()
+            .map(Deck::from)
+            .collect();
+
+        let combos = load_combos(&self.app.workspace)?;
+        self.violations.clear();
+        self.brackets.clear();
+        for deck in self.decks.iter() {
+            self.violations.push(deck.validate(&self.app.store).await?);
+            self.brackets
+                .push(is_commander_format(&deck.format).then(|| estimate_bracket(deck, &combos)));
+        }
+
+        if !self.decks.is_empty() && self.deck_list.selected().is_none() {
+            self.deck_list.select_first();
+        }
+
+        Ok(())
+    }
+
+    pub fn selected_deck(&self) -> Option<&Deck> {
+        self.deck_list.selected().and_then(|i| self.decks.get(i))
+    }
+
+    /// Rule violations of the selected deck
+    pub fn selected_violations(&self) -> &[Violation] {
+        self.deck_list
+            .selected()
+            .and_then(|i| self.violations.get(i))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
     }
 }
 
 #[async_trait]
 impl<'a> Component for MainScreen<'a> {
-    fn render(&mut self, frame: &mut Frame) {}
+    fn render(&mut self, frame: &mut Frame) {
+        let items = self
+            .decks
+            .iter()
+            .zip(self.violations.iter())
+            .zip(self.brackets.iter())
+            .map(|((deck, violations), bracket)| {
+                let mut line = format!(
+                    "{} ({}) - {} cards, {} sideboard",
+                    deck.name,
+                    deck.format,
+                    deck.deck_size(),
+                    deck.count(Zone::Sideboard)
+                );
+
+                if let Some(bracket) = bracket {
+                    line.push_str(&format!(" [bracket {}]", bracket.bracket));
+                }
 
-    async fn handle_event(&mut self, event: ()) -> Result<EventResult> {
+                if !violations.is_empty() {
+                    line.push_str(&format!(" [{} issues]", violations.len()));
+                }
+
+                ListItem::new(line)
+            })
+            .collect::<Vec<ListItem>>();
+
+        let list = List::new(items)
+            .block(Block::default().title("Decks").borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        let [decks, prompt] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(3)]).areas(frame.area());
+        frame.render_stateful_widget(list, decks, &mut self.deck_list);
+
+        let (title, text) = match self.mode {
+            AppMode::Editing => match self.prompt {
+                Prompt::Import => ("Import decklist", self.input.clone()),
+                Prompt::Export => ("Export deck to", self.input.clone()),
+            },
+            AppMode::Normal => (
+                "",
+                self.status.clone().unwrap_or_else(|| {
+                    "Enter: open deck, i: import decklist, e: export deck, Esc: quit".to_string()
+                }),
+            ),
+        };
+        let prompt_block =
+            Paragraph::new(text).block(Block::default().title(title).borders(Borders::ALL));
+        frame.render_widget(prompt_block, prompt);
+    }
+
+    async fn handle_event(&mut self, _event: ()) -> Result<EventResult> {
         if let Event::Key(key) = event::read()? {
-            let result = match self.mode {
+            match self.mode {
                 AppMode::Normal => match key.code {
                     KeyCode::Esc => Ok(EventResult::Quit),
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        self.deck_list.select_next();
+                        Ok(EventResult::Render)
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        self.deck_list.select_previous();
+                        Ok(EventResult::Render)
+                    }
+                    KeyCode::Char('i') => {
+                        self.mode = AppMode::Editing;
+                        self.prompt = Prompt::Import;
+                        self.input.clear();
+                        Ok(EventResult::Render)
+                    }
+                    KeyCode::Enter if self.deck_list.selected().is_some() => {
+                        Ok(EventResult::Switch(AppState::DeckEdit))
+                    }
+                    KeyCode::Char('e') if self.deck_list.selected().is_some() => {
+                        self.mode = AppMode::Editing;
+                        self.prompt = Prompt::Export;
+                        self.input.clear();
+                        Ok(EventResult::Render)
+                    }
                     _ => Ok(EventResult::Render),
                 },
-                AppMode::Editing => Ok(EventResult::Render),
-            };
+                AppMode::Editing => {
+                    match key.code {
+                        KeyCode::Esc => self.mode = AppMode::Normal,
+                        KeyCode::Backspace => {
+                            self.input.pop();
+                        }
+                        KeyCode::Char(c) => self.input.push(c),
+                        KeyCode::Enter => {
+                            self.mode = AppMode::Normal;
+                            let result = match self.prompt {
+                                Prompt::Import => self.import_deck().await,
+                                Prompt::Export => self.export_deck(),
+                            };
+
+                            if let Err(e) = result {
+                                self.status = Some(format!("{:?} failed: {e:#}", self.prompt));
+                            }
+                        }
+                        _ => {}
+                    }
 
-            result
+                    Ok(EventResult::Render)
+                }
+            }
         } else {
             Ok(EventResult::Render)
         }
@@ -82,6 +276,8 @@ pub struct Tui<'a> {
     terminal: DefaultTerminal,
 
     main_state: MainScreen<'a>,
+    deck_state: DeckView<'a>,
+    playtest_state: PlaytestScreen,
 }
 
 impl<'a> Tui<'a> {
@@ -89,11 +285,55 @@ impl<'a> Tui<'a> {
         Self {
             state: AppState::MainScreen,
             terminal: ratatui::init(),
-            main_state: MainScreen::new(&store),
+            main_state: MainScreen::new(store),
+            deck_state: DeckView::new(store),
+            playtest_state: PlaytestScreen::new(),
         }
     }
 
     pub async fn run(&mut self) -> Result<()> {
+        self.main_state.load_all_decks().await?;
+
+        loop {
+            self.terminal.draw(|frame| match self.state {
+                AppState::MainScreen => self.main_state.render(frame),
+                AppState::DeckEdit => self.deck_state.render(frame),
+                AppState::Playtest => self.playtest_state.render(frame),
+            })?;
+
+            let result = match self.state {
+                AppState::MainScreen => self.main_state.handle_event(()).await?,
+                AppState::DeckEdit => self.deck_state.handle_event(()).await?,
+                AppState::Playtest => self.playtest_state.handle_event(()).await?,
+            };
+
+            match result {
+                EventResult::Quit => break,
+                EventResult::Switch(AppState::DeckEdit) if self.state == AppState::Playtest => {
+                    self.state = AppState::DeckEdit;
+                }
+                EventResult::Switch(AppState::DeckEdit) => {
+                    if let Some(deck) = self.main_state.selected_deck() {
+                        let violations = self.main_state.selected_violations().to_vec();
+                        self.deck_state.open(deck.clone(), violations).await?;
+                        self.state = AppState::DeckEdit;
+                    }
+                }
+                EventResult::Switch(AppState::MainScreen) => {
+                    // Tags may have changed while the deck was open
+                    self.main_state.load_all_decks().await?;
+                    self.state = AppState::MainScreen;
+                }
+                EventResult::Switch(AppState::Playtest) => {
+                    if let Some(deck) = self.deck_state.deck() {
+                        self.playtest_state.open(deck);
+                        self.state = AppState::Playtest;
+                    }
+                }
+                EventResult::Render => {}
+            }
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,262 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ratatui::{
+    Frame,
+    crossterm::event::{self, Event, KeyCode},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+
+use super::{AppMode, AppState, Component, EventResult};
+use crate::data::{
+    Deck,
+    playtest::{GameZone, Playtest},
+};
+
+const HELP: &str = "Tab: zone, m: mulligan, b: bottom, y: keep, d: draw, n: next turn, p: play, t: tap, g: graveyard, x: exile, h: hand, l: library top, r: restart, s: seed, Esc: back";
+
+/// Goldfish a deck, drawing and playing cards without an opponent
+#[derive(Debug)]
+pub struct PlaytestScreen {
+    mode: AppMode,
+    game: Option<Playtest>,
+    focus: usize,
+    lists: [ListState; GameZone::VISIBLE.len()],
+    input: String,
+    status: Option<String>,
+}
+
+impl PlaytestScreen {
+    pub fn new() -> Self {
+        Self {
+            mode: AppMode::Normal,
+            game: None,
+            focus: 0,
+            lists: Default::default(),
+            input: String::new(),
+            status: None,
+        }
+    }
+
+    /// Start a game with a seed from the clock, shown on screen so it can be replayed
+    pub fn open(&mut self, deck: &Deck) {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+
+        self.game = Some(Playtest::new(deck, seed, false));
+        self.mode = AppMode::Normal;
+        self.focus = 0;
+        self.status = None;
+        self.reset_selection();
+    }
+
+    fn reset_selection(&mut self) {
+        for list in self.lists.iter_mut() {
+            *list = ListState::default();
+            list.select_first();
+        }
+    }
+
+    fn zone(&self) -> GameZone {
+        GameZone::VISIBLE[self.focus]
+    }
+
+    fn selected(&self) -> Option<usize> {
+        let game = self.game.as_ref()?;
+        self.lists[self.focus]
+            .selected()
+            .filter(|i| *i < game.cards(self.zone()).len())
+    }
+
+    /// Move the selected card to another zone
+    fn move_selected(&mut self, to: GameZone) {
+        let from = self.zone();
+        if let (Some(index), Some(game)) = (self.selected(), self.game.as_mut()) {
+            self.status = (!game.move_card(from, index, to))
+                .then(|| "Keep a hand before playing cards".into());
+        }
+    }
+
+    fn restart_with_seed(&mut self) -> Result<()> {
+        let input = std::mem::take(&mut self.input);
+        let seed = input.trim().parse().context("seed should be a number")?;
+        if let Some(game) = self.game.as_mut() {
+            game.reseed(seed);
+        }
+        self.reset_selection();
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Component for PlaytestScreen {
+    fn render(&mut self, frame: &mut Frame) {
+        let Some(game) = &self.game else {
+            return;
+        };
+
+        let mut header = format!(
+            "Turn {} - library {} - seed {}",
+            game.turn,
+            game.library.len(),
+            game.seed()
+        );
+        if game.turn == 0 {
+            header.push_str(" - opening hand");
+            if game.to_bottom > 0 {
+                header.push_str(&format!(", put {} on the bottom", game.to_bottom));
+            }
+        }
+
+        let [top, zones, prompt] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(1),
+            Constraint::Length(3),
+        ])
+        .areas(frame.area());
+        frame.render_widget(
+            Paragraph::new(header).block(Block::default().title("Playtest").borders(Borders::ALL)),
+            top,
+        );
+
+        let [hand, battlefield, side] = Layout::horizontal([
+            Constraint::Percentage(35),
+            Constraint::Percentage(35),
+            Constraint::Percentage(30),
+        ])
+        .areas(zones);
+        let [graveyard, exile, command] =
+            Layout::vertical([Constraint::Ratio(1, 3); 3]).areas(side);
+
+        for (i, (zone, area)) in GameZone::VISIBLE
+            .into_iter()
+            .zip([hand, battlefield, graveyard, exile, command])
+            .enumerate()
+        {
+            let items = game
+                .cards(zone)
+                .iter()
+                .map(|card| {
+                    let mut text = card.card.name.clone();
+                    if card.tapped {
+                        text.push_str(" (tapped)");
+                    }
+                    ListItem::new(text)
+                })
+                .collect::<Vec<ListItem>>();
+
+            let mut block = Block::default()
+                .title(format!("{zone:?} ({})", items.len()))
+                .borders(Borders::ALL);
+            if i == self.focus {
+                block = block.border_style(Style::default().add_modifier(Modifier::BOLD));
+            }
+
+            let mut list = List::new(items).block(block);
+            if i == self.focus {
+                list = list.highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            }
+            frame.render_stateful_widget(list, area, &mut self.lists[i]);
+        }
+
+        let (title, text) = match self.mode {
+            AppMode::Editing => ("Restart with seed", self.input.clone()),
+            AppMode::Normal => ("", self.status.clone().unwrap_or(HELP.to_string())),
+        };
+        frame.render_widget(
+            Paragraph::new(text).block(Block::default().title(title).borders(Borders::ALL)),
+            prompt,
+        );
+    }
+
+    async fn handle_event(&mut self, _event: ()) -> Result<EventResult> {
+        let Event::Key(key) = event::read()? else {
+            return Ok(EventResult::Render);
+        };
+
+        if self.mode == AppMode::Editing {
+            match key.code {
+                KeyCode::Esc => self.mode = AppMode::Normal,
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                KeyCode::Char(c) => self.input.push(c),
+                KeyCode::Enter => {
+                    self.mode = AppMode::Normal;
+                    if let Err(e) = self.restart_with_seed() {
+                        self.status = Some(format!("Restart failed: {e:#}"));
+                    }
+                }
+                _ => {}
+            }
+
+            return Ok(EventResult::Render);
+        }
+
+        self.status = None;
+        let zone = self.zone();
+        let selected = self.selected();
+        let Some(game) = self.game.as_mut() else {
+            return Ok(EventResult::Switch(AppState::DeckEdit));
+        };
+
+        match key.code {
+            KeyCode::Esc => return Ok(EventResult::Switch(AppState::DeckEdit)),
+            KeyCode::Tab => self.focus = (self.focus + 1) % GameZone::VISIBLE.len(),
+            KeyCode::BackTab => {
+                self.focus = (self.focus + GameZone::VISIBLE.len() - 1) % GameZone::VISIBLE.len()
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.lists[self.focus].select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.lists[self.focus].select_previous(),
+            KeyCode::Char('m') => {
+                self.status =
+                    (!game.mulligan()).then(|| "Can only mulligan before keeping a hand".into());
+            }
+            KeyCode::Char('b') => {
+                let bottomed = zone == GameZone::Hand && selected.is_some_and(|i| game.bottom(i));
+                self.status =
+                    (!bottomed).then(|| "Select a card in hand to bottom after a mulligan".into());
+            }
+            KeyCode::Char('y') => {
+                self.status =
+                    (!game.keep()).then(|| "Put the mulligan cards on the bottom first".into());
+            }
+            KeyCode::Char('d') => {
+                self.status = (!game.draw()).then(|| {
+                    match game.turn {
+                        0 => "Keep a hand before drawing",
+                        _ => "The library is empty",
+                    }
+                    .into()
+                });
+            }
+            KeyCode::Char('n') => game.next_turn(),
+            KeyCode::Char('t') if zone == GameZone::Battlefield => {
+                if let Some(index) = selected {
+                    game.toggle_tap(index);
+                }
+            }
+            KeyCode::Char('p') => self.move_selected(GameZone::Battlefield),
+            KeyCode::Char('g') => self.move_selected(GameZone::Graveyard),
+            KeyCode::Char('x') => self.move_selected(GameZone::Exile),
+            KeyCode::Char('h') => self.move_selected(GameZone::Hand),
+            KeyCode::Char('l') => self.move_selected(GameZone::Library),
+            KeyCode::Char('r') => {
+                game.restart();
+                self.reset_selection();
+            }
+            KeyCode::Char('s') => {
+                self.mode = AppMode::Editing;
+                self.input.clear();
+            }
+            _ => {}
+        }
+
+        Ok(EventResult::Render)
+    }
+}